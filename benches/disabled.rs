@@ -0,0 +1,37 @@
+//! Cost of disabled debug prints in a tight loop like the one in `demo/demo.rs`.
+//!
+//! Run with `cargo bench`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use debug_macros::debug;
+
+fn disabled(c: &mut Criterion) {
+    debug_macros::set_debug(false);
+    let mut group = c.benchmark_group("disabled");
+    group.bench_function("empty", |b| b.iter(|| black_box(3)));
+    group.bench_function("is_debug", |b| {
+        b.iter(|| {
+            if debug_macros::is_debug() {
+                black_box(3);
+            }
+        })
+    });
+    group.bench_function("debug", |b| {
+        b.iter(|| {
+            let i = black_box(3);
+            debug!("round", i);
+        })
+    });
+    debug_macros::set_filter("other::module=on").unwrap();
+    group.bench_function("debug_filtered", |b| {
+        b.iter(|| {
+            let i = black_box(3);
+            debug!("round", i);
+        })
+    });
+    debug_macros::clear_filter();
+    group.finish();
+}
+
+criterion_group!(benches, disabled);
+criterion_main!(benches);
@@ -0,0 +1,105 @@
+//! Per-callsite caching of the enable check.
+
+use crate::{module_level, Level};
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+
+/// Bumped whenever a setting that affects [module_level] changes, invalidating every
+/// [Callsite]. Starts at 1 so that a fresh callsite never matches.
+static GENERATION: AtomicUsize = AtomicUsize::new(1);
+
+/// Bits of [Callsite] state holding the cached level.
+const LEVEL_BITS: u32 = 3;
+const LEVEL_MASK: usize = (1 << LEVEL_BITS) - 1;
+
+/// Invalidate the cached levels of all callsites. Called after the settings have changed.
+pub(crate) fn invalidate() {
+    GENERATION.fetch_add(1, Release);
+}
+
+/// Cache of the [module_level] of one macro invocation, so that a disabled call costs a few
+/// relaxed loads and a comparison. Per-thread overrides are not cached. Each invocation of the
+/// macros has one in a `static`.
+#[doc(hidden)]
+pub struct Callsite {
+    /// Generation the cached level was computed in, shifted left by [LEVEL_BITS], plus the
+    /// level as `level as usize`.
+    state: AtomicUsize,
+}
+
+impl Callsite {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Callsite {
+            state: AtomicUsize::new(0),
+        }
+    }
+
+    /// Report whether messages at `level` from this callsite, in the module at `module_path`,
+    /// are currently reported.
+    #[inline]
+    pub fn is_enabled(&self, level: Level, module_path: &str) -> bool {
+        if let Some(max) = crate::overrides::thread_level() {
+            return max.is_some_and(|max| level <= max);
+        }
+        let state = self.state.load(Relaxed);
+        let max = if state >> LEVEL_BITS == GENERATION.load(Relaxed) {
+            state & LEVEL_MASK
+        } else {
+            self.refresh(module_path)
+        };
+        level as usize <= max
+    }
+
+    /// Recompute and cache the level of this callsite.
+    #[cold]
+    fn refresh(&self, module_path: &str) -> usize {
+        // Acquire pairs with the Release in `invalidate`, so that the settings read below are
+        // at least as new as the generation they are cached under.
+        let generation = GENERATION.load(Acquire);
+        let max = module_level(module_path).map_or(0, |l| l as usize);
+        self.state.store(generation << LEVEL_BITS | max, Relaxed);
+        max
+    }
+}
+
+/// The `tracing` callsite of one invocation of the macros, made when it first emits an event.
+/// Empty without the `tracing` feature. Each invocation of the macros that reports through
+/// [__emit_record](crate::__emit_record) has one in a `static`.
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct EventSite {
+    #[cfg(feature = "tracing")]
+    pub(crate) metadata: std::sync::OnceLock<tracing::Metadata<'static>>,
+    #[cfg(feature = "tracing")]
+    pub(crate) registered: std::sync::Once,
+}
+
+impl EventSite {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        EventSite {
+            #[cfg(feature = "tracing")]
+            metadata: std::sync::OnceLock::new(),
+            #[cfg(feature = "tracing")]
+            registered: std::sync::Once::new(),
+        }
+    }
+}
+
+#[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
+fn test_callsite() {
+    use crate::{set_filter, set_level};
+    let _lock = crate::test_lock();
+    let callsite = Callsite::new();
+    set_level(Some(Level::Info));
+    assert!(callsite.is_enabled(Level::Info, "cs::a"));
+    assert!(!callsite.is_enabled(Level::Debug, "cs::a"));
+    set_filter("cs::a=trace").unwrap();
+    assert!(callsite.is_enabled(Level::Trace, "cs::a"));
+    set_filter("").unwrap();
+    set_level(None);
+    assert!(!callsite.is_enabled(Level::Error, "cs::a"));
+}
@@ -0,0 +1,85 @@
+//! Capturing output in tests.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+type Buffer = Rc<RefCell<Vec<String>>>;
+
+thread_local! {
+    /// Buffer of the innermost active [Capture] on this thread.
+    static CAPTURE: RefCell<Option<Buffer>> = const { RefCell::new(None) };
+}
+
+/// Guard returned by [capture]. While it is alive, output of the per-level macros on the
+/// current thread goes to its buffer instead of the installed [Sink](crate::Sink). Dropping it
+/// restores the previous destination.
+#[must_use = "output is captured only while the guard is alive"]
+#[derive(Debug)]
+pub struct Capture {
+    lines: Buffer,
+    previous: Option<Buffer>,
+}
+
+/// Capture the output of [debug](crate::debug) and the other per-level macros on the current
+/// thread until the returned guard is dropped. Captures nest: only the innermost sees output.
+///
+/// This is intended for unit tests, which run in parallel on separate threads and so cannot
+/// share a global sink.
+///
+/// # Examples
+///
+/// ```
+/// # if !debug_macros::__STATIC_ENABLED { return; }
+/// use debug_macros::debug;
+/// debug_macros::set_debug(true);
+/// let cap = debug_macros::capture();
+/// debug!("checking", 1, 2);
+/// assert_eq!(cap.lines(), vec!["debug: checking: 1, 2"]);
+/// ```
+pub fn capture() -> Capture {
+    let lines = Buffer::default();
+    let previous = CAPTURE.with(|c| c.replace(Some(Rc::clone(&lines))));
+    Capture { lines, previous }
+}
+
+impl Capture {
+    /// The lines captured so far.
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.borrow().clone()
+    }
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        CAPTURE.with(|c| *c.borrow_mut() = self.previous.take());
+    }
+}
+
+/// Append `line` to the active capture buffer on this thread, if any. Returns `false` if there
+/// is none.
+pub(crate) fn capture_line(line: &str) -> bool {
+    CAPTURE.with(|c| match &*c.borrow() {
+        Some(lines) => {
+            lines.borrow_mut().push(line.to_string());
+            true
+        }
+        None => false,
+    })
+}
+
+#[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
+fn test_capture() {
+    use crate::{debug, set_debug};
+    let _lock = crate::test_lock();
+    set_debug(true);
+    let outer = capture();
+    debug!("outer");
+    {
+        let inner = capture();
+        debug!("inner", 1);
+        assert_eq!(inner.lines(), vec!["debug: inner: 1"]);
+    }
+    debug!("outer again");
+    assert_eq!(outer.lines(), vec!["debug: outer", "debug: outer again"]);
+}
@@ -0,0 +1,274 @@
+//! Collapsing runs of repeated output lines.
+
+use crate::format::{output_format, strip_timestamp, OutputFormat};
+use crate::Level;
+
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Deduplication state.
+struct Dedup {
+    /// Flush timeout, or `None` when deduplication is off.
+    timeout: Option<Duration>,
+    /// Last line written, and the same without its timestamp for comparison, with the level and
+    /// target of its message.
+    line: String,
+    key: String,
+    level: Level,
+    target: String,
+    /// Output format of `line`, which determines how repeats of it are summarized.
+    format: OutputFormat,
+    /// Number of repeats of `line` suppressed since it was last written.
+    repeats: usize,
+    /// When `line` was last written.
+    since: Option<Instant>,
+    /// Whether the flusher thread is running.
+    flusher: bool,
+}
+
+static DEDUP: Mutex<Dedup> = Mutex::new(Dedup {
+    timeout: None,
+    line: String::new(),
+    key: String::new(),
+    level: Level::Debug,
+    target: String::new(),
+    format: OutputFormat::Text,
+    repeats: 0,
+    since: None,
+    flusher: false,
+});
+
+/// True while deduplication is on, to skip taking the lock in the common case.
+static ON: AtomicBool = AtomicBool::new(false);
+
+/// A summary of repeated lines, with the level and target of their message, to be written once
+/// the lock is released.
+type Summary = (Level, String, String);
+
+fn lock() -> MutexGuard<'static, Dedup> {
+    DEDUP.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Collapse runs of identical lines written to the [Sink](crate::Sink) into one, in the manner
+/// of syslog, or turn this off with `None`. Off by default.
+///
+/// The first line of a run is written as usual. Repeats of it are counted instead, and written
+/// as a single `line (repeated N times)` when a different line is written, when [flush_dedup]
+/// is called, or once `timeout` has passed since the line was last written, whichever is first.
+/// In [OutputFormat::Json], the count is instead a `"repeated":N` member of the line's object,
+/// and in [OutputFormat::Logfmt] a `repeated=N` pair.
+/// Lines are compared without their [timestamps](crate::set_timestamps).
+///
+/// The sink is called after the deduplication state is unlocked, so it may itself write
+/// messages, though lines from different threads may then be written in a different order than
+/// they were compared.
+pub fn set_dedup(timeout: Option<Duration>) {
+    let mut dedup = lock();
+    let summary = dedup.summary();
+    dedup.timeout = timeout;
+    ON.store(timeout.is_some(), Relaxed);
+    dedup.key.clear();
+    dedup.line.clear();
+    if timeout.is_some() && !dedup.flusher {
+        dedup.flusher = true;
+        std::thread::spawn(flusher);
+    }
+    drop(dedup);
+    let _ = write_summary(summary);
+}
+
+/// Report the deduplication timeout, or `None` if deduplication is off.
+pub fn dedup() -> Option<Duration> {
+    lock().timeout
+}
+
+/// Write out any pending count of repeated lines. Call this before exiting when using
+/// [set_dedup].
+pub fn flush_dedup() -> io::Result<()> {
+    let summary = lock().summary();
+    write_summary(summary)
+}
+
+impl Dedup {
+    /// Take the summary of any pending repeats, to be written with [write_summary].
+    fn summary(&mut self) -> Option<Summary> {
+        if self.repeats == 0 {
+            return None;
+        }
+        let summary = match self.format {
+            OutputFormat::Text => {
+                let times = if self.repeats == 1 { "time" } else { "times" };
+                format!("{} (repeated {} {})", self.line, self.repeats, times)
+            }
+            OutputFormat::Json => {
+                let object = self.line.strip_suffix('}').unwrap_or(&self.line);
+                format!("{},\"repeated\":{}}}", object, self.repeats)
+            }
+            OutputFormat::Logfmt => format!("{} repeated={}", self.line, self.repeats),
+        };
+        self.repeats = 0;
+        self.since = Some(Instant::now());
+        Some((self.level, self.target.clone(), summary))
+    }
+}
+
+/// Write `summary`, if any, to the installed sink.
+fn write_summary(summary: Option<Summary>) -> io::Result<()> {
+    match summary {
+        Some((level, target, line)) => crate::sink::write_sink(level, &target, &line),
+        None => Ok(()),
+    }
+}
+
+/// Flush pending repeats on timeout until deduplication is turned off.
+fn flusher() {
+    loop {
+        let (timeout, summary) = {
+            let mut dedup = lock();
+            let timeout = match dedup.timeout {
+                Some(timeout) => timeout,
+                None => {
+                    dedup.flusher = false;
+                    return;
+                }
+            };
+            let mut summary = None;
+            if dedup.since.is_some_and(|since| since.elapsed() >= timeout) {
+                summary = dedup.summary();
+            }
+            (timeout, summary)
+        };
+        let _ = write_summary(summary);
+        std::thread::sleep(timeout.min(Duration::from_millis(100)));
+    }
+}
+
+/// Write `line`, a message at `level` from `target`, to the installed sink, collapsing repeats
+/// if deduplication is on.
+pub(crate) fn emit(level: Level, target: &str, line: &str) -> io::Result<()> {
+    if !ON.load(Relaxed) {
+        return crate::sink::write_sink(level, target, line);
+    }
+    let mut dedup = lock();
+    let timeout = match dedup.timeout {
+        Some(timeout) => timeout,
+        None => {
+            drop(dedup);
+            return crate::sink::write_sink(level, target, line);
+        }
+    };
+    let key = strip_timestamp(line);
+    if key == dedup.key {
+        dedup.line = line.to_string();
+        dedup.repeats += 1;
+        let mut summary = None;
+        if dedup.since.is_some_and(|since| since.elapsed() >= timeout) {
+            summary = dedup.summary();
+        }
+        drop(dedup);
+        return write_summary(summary);
+    }
+    let summary = dedup.summary();
+    dedup.key = key.to_string();
+    dedup.line = line.to_string();
+    dedup.level = level;
+    dedup.target = target.to_string();
+    dedup.format = output_format();
+    dedup.since = Some(Instant::now());
+    drop(dedup);
+    write_summary(summary).and(crate::sink::write_sink(level, target, line))
+}
+
+#[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
+fn test_dedup() {
+    use crate::{debug, reset_sink, set_debug, set_sink, MemorySink};
+    let _lock = crate::test_lock();
+    set_debug(true);
+    let buffer = MemorySink::new();
+    set_sink(Box::new(buffer.clone()));
+    set_dedup(Some(Duration::from_secs(3600)));
+    for i in 0..6 {
+        debug!("polling", n = i / 3);
+    }
+    debug!("done");
+    debug!("done");
+    flush_dedup().unwrap();
+    debug!("done");
+    set_dedup(None);
+    debug!("done");
+    reset_sink();
+    assert_eq!(
+        buffer.lines(),
+        vec![
+            "debug: polling: n = 0",
+            "debug: polling: n = 0 (repeated 2 times)",
+            "debug: polling: n = 1",
+            "debug: polling: n = 1 (repeated 2 times)",
+            "debug: done",
+            "debug: done (repeated 1 time)",
+            "debug: done (repeated 1 time)",
+            "debug: done",
+        ],
+    );
+}
+
+#[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
+fn test_dedup_json() {
+    use crate::{debug, reset_sink, set_debug, set_output_format, set_sink, MemorySink};
+    let _lock = crate::test_lock();
+    set_debug(true);
+    let buffer = MemorySink::new();
+    set_sink(Box::new(buffer.clone()));
+    set_output_format(OutputFormat::Json);
+    set_dedup(Some(Duration::from_secs(3600)));
+    let line = line!() + 2;
+    for _ in 0..3 {
+        debug!("poll", n = 1);
+    }
+    set_dedup(None);
+    set_output_format(OutputFormat::Text);
+    reset_sink();
+    let expected = format!(
+        concat!(
+            r#"{{"level":"debug","msg":"poll","fields":{{"n":"1"}},"#,
+            r#""module":"debug_macros::dedup","file":"{}","line":{},"column":9"#,
+        ),
+        file!(),
+        line,
+    );
+    assert_eq!(
+        buffer.lines(),
+        vec![
+            format!("{}}}", expected),
+            format!(r#"{},"repeated":2}}"#, expected),
+        ],
+    );
+}
+
+#[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
+fn test_dedup_logfmt() {
+    use crate::{debug, reset_sink, set_debug, set_output_format, set_sink, MemorySink};
+    let _lock = crate::test_lock();
+    set_debug(true);
+    let buffer = MemorySink::new();
+    set_sink(Box::new(buffer.clone()));
+    set_output_format(OutputFormat::Logfmt);
+    set_dedup(Some(Duration::from_secs(3600)));
+    let line = line!() + 2;
+    for _ in 0..3 {
+        debug!("poll", n = 1);
+    }
+    set_dedup(None);
+    set_output_format(OutputFormat::Text);
+    reset_sink();
+    let expected = format!("level=debug msg=poll n=1 file={} line={}", file!(), line);
+    assert_eq!(
+        buffer.lines(),
+        vec![expected.clone(), format!("{} repeated=2", expected)],
+    );
+}
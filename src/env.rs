@@ -0,0 +1,66 @@
+//! Configuration from the environment.
+
+use crate::filter::{self, FilterError};
+
+use std::sync::Once;
+
+/// Name of the environment variable read by [init_from_env].
+pub const ENV_VAR: &str = "DEBUG_MACROS";
+
+/// Guards the lazy read of [ENV_VAR] on first use.
+static INIT: Once = Once::new();
+
+/// Apply [ENV_VAR], if set, without triggering lazy initialization.
+fn apply_env() -> Result<(), FilterError> {
+    match std::env::var(ENV_VAR) {
+        Ok(spec) => filter::apply(&spec),
+        Err(_) => Ok(()),
+    }
+}
+
+/// Configure debugging from the [ENV_VAR] (`DEBUG_MACROS`) environment variable, whose value is
+/// a filter spec as for [set_filter](crate::set_filter): for example `DEBUG_MACROS=on` or
+/// `DEBUG_MACROS=warn,mycrate::parser=trace`. Nothing is changed if the variable is unset.
+///
+/// This happens automatically the first time any debug state is read or set, in which case a
+/// malformed spec is reported on [std::io::stderr], bypassing the installed
+/// [Sink](crate::Sink). Calling it explicitly rereads the variable and returns a malformed spec
+/// as an error.
+///
+/// The `debug_emit` feature only sets whether debugging is on by default, so this can turn
+/// debugging on in any build, including release builds, unless the macros are compiled out
+/// with the `debug_static` feature.
+pub fn init_from_env() -> Result<(), FilterError> {
+    INIT.call_once(|| {
+        crate::time::start();
+    });
+    apply_env()
+}
+
+/// Read [ENV_VAR] if this has not yet been done. Called on first use of the debug state, so that
+/// explicit settings made in code take precedence over the environment. This also marks the
+/// process start for elapsed-time timestamps.
+pub(crate) fn lazy_init() {
+    INIT.call_once(|| {
+        crate::time::start();
+        if let Err(e) = apply_env() {
+            eprintln!("debug_macros: {}: {}", ENV_VAR, e);
+        }
+    });
+}
+
+#[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
+fn test_init_from_env() {
+    use crate::{is_module_enabled, level, set_level, Level};
+    let _lock = crate::test_lock();
+    std::env::set_var(ENV_VAR, "error,envtest::a=trace");
+    init_from_env().unwrap();
+    assert_eq!(level(), Some(Level::Error));
+    assert!(is_module_enabled(Level::Trace, "envtest::a"));
+    std::env::set_var(ENV_VAR, "envtest::a=sometimes");
+    assert!(init_from_env().is_err());
+    std::env::remove_var(ENV_VAR);
+    crate::clear_filter();
+    set_level(Some(Level::Debug));
+}
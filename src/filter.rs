@@ -0,0 +1,151 @@
+//! Per-module filtering of debug output.
+
+use crate::env::lazy_init;
+use crate::{callsite, store_level, Level};
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::sync::RwLock;
+
+/// Module filter table: module path and the level it reports, if any.
+static FILTERS: RwLock<Vec<(String, Option<Level>)>> = RwLock::new(Vec::new());
+
+/// True when [FILTERS] is nonempty, to skip taking the lock in the common case.
+static HAS_FILTERS: AtomicBool = AtomicBool::new(false);
+
+/// Error returned by [set_filter] for a malformed filter spec.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterError {
+    directive: String,
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid debug filter directive `{}`", self.directive)
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+/// Parse a filter value: `on` (meaning [Level::Debug]), `off`, or a [Level] name.
+fn parse_value(value: &str) -> Option<Option<Level>> {
+    match value {
+        "on" => Some(Some(Level::Debug)),
+        "off" => Some(None),
+        _ => value.parse().ok().map(Some),
+    }
+}
+
+/// Replace the module filter table from a comma-separated `spec` such as
+/// `mycrate::parser=on,mycrate::net=off`.
+///
+/// Each directive is one of
+///
+/// * `path=value`: report messages from module `path` and its submodules at `value`, which is
+///   `on` (the same as `debug`), `off`, or a [Level] name such as `trace`.
+/// * `path`: the same as `path=on`.
+/// * `value`: set the global level as with [set_level](crate::set_level), for modules not
+///   otherwise listed.
+///
+/// When several paths match a module, the longest wins. On error, no state is changed.
+///
+/// # Examples
+///
+/// ```
+/// # if !debug_macros::__STATIC_ENABLED { return; }
+/// use debug_macros::{is_module_enabled, set_filter, Level};
+/// set_filter("off,mycrate::parser=on,mycrate::parser::lexer=off").unwrap();
+/// assert!(is_module_enabled(Level::Debug, "mycrate::parser"));
+/// assert!(is_module_enabled(Level::Debug, "mycrate::parser::ast"));
+/// assert!(!is_module_enabled(Level::Debug, "mycrate::parser::lexer"));
+/// assert!(!is_module_enabled(Level::Debug, "mycrate::net"));
+/// ```
+pub fn set_filter(spec: &str) -> Result<(), FilterError> {
+    lazy_init();
+    apply(spec)
+}
+
+/// Implementation of [set_filter], without triggering lazy initialization.
+pub(crate) fn apply(spec: &str) -> Result<(), FilterError> {
+    let mut global = None;
+    let mut filters = Vec::new();
+    for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+        let error = || FilterError {
+            directive: directive.to_string(),
+        };
+        let mut parts = directive.splitn(2, '=');
+        let path = parts.next().unwrap().trim();
+        match parts.next() {
+            Some(value) => {
+                let level = parse_value(value.trim()).ok_or_else(error)?;
+                if path.is_empty() {
+                    return Err(error());
+                }
+                filters.push((path.to_string(), level));
+            }
+            None => match parse_value(path) {
+                Some(level) => global = Some(level),
+                None => filters.push((path.to_string(), Some(Level::Debug))),
+            },
+        }
+    }
+    if let Some(level) = global {
+        store_level(level);
+    }
+    set_filters(filters);
+    Ok(())
+}
+
+/// Remove all module filters, so that only the global level applies.
+pub fn clear_filter() {
+    lazy_init();
+    set_filters(Vec::new());
+}
+
+fn set_filters(filters: Vec<(String, Option<Level>)>) {
+    let mut table = FILTERS.write().unwrap_or_else(|e| e.into_inner());
+    HAS_FILTERS.store(!filters.is_empty(), Relaxed);
+    *table = filters;
+    drop(table);
+    callsite::invalidate();
+}
+
+/// True if `module_path` is `path` or one of its submodules.
+fn matches(path: &str, module_path: &str) -> bool {
+    module_path.starts_with(path)
+        && (module_path.len() == path.len() || module_path[path.len()..].starts_with("::"))
+}
+
+/// Find the level for `module_path` in the filter table, or `None` if no filter matches.
+pub(crate) fn lookup(module_path: &str) -> Option<Option<Level>> {
+    lazy_init();
+    if !HAS_FILTERS.load(Relaxed) {
+        return None;
+    }
+    let table = FILTERS.read().unwrap_or_else(|e| e.into_inner());
+    table
+        .iter()
+        .filter(|(path, _)| matches(path, module_path))
+        .max_by_key(|(path, _)| path.len())
+        .map(|&(_, level)| level)
+}
+
+#[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
+fn test_filter() {
+    use crate::{is_module_enabled, level};
+    let _lock = crate::test_lock();
+    set_filter("info, a::b=trace, a::b::c, a::d=off").unwrap();
+    assert_eq!(level(), Some(Level::Info));
+    assert!(is_module_enabled(Level::Trace, "a::b"));
+    assert!(is_module_enabled(Level::Debug, "a::b::c::x"));
+    assert!(!is_module_enabled(Level::Trace, "a::b::c"));
+    assert!(!is_module_enabled(Level::Error, "a::d"));
+    assert!(is_module_enabled(Level::Info, "a::bb"));
+    assert!(!is_module_enabled(Level::Debug, "a::bb"));
+    assert!(set_filter("a=loud").is_err());
+    assert!(set_filter("=on").is_err());
+    assert!(is_module_enabled(Level::Trace, "a::b"));
+    clear_filter();
+    assert!(!is_module_enabled(Level::Trace, "a::b"));
+}
@@ -0,0 +1,260 @@
+//! Formatting of messages into output lines.
+
+use crate::time::{timestamps, Timestamps};
+use crate::{Level, SeqCst};
+
+use std::fmt::{self, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize};
+
+/// A message as captured by the writing macros at their call site.
+#[doc(hidden)]
+pub struct Record<'a> {
+    pub level: Level,
+    pub module_path: &'a str,
+    /// Source location; `file` is empty if unknown, and `column` is `0` if unknown.
+    pub file: &'a str,
+    pub line: u32,
+    pub column: u32,
+    pub msg: Option<fmt::Arguments<'a>>,
+    pub fields: &'a [(Option<&'static str>, &'a dyn fmt::Debug)],
+    pub pretty: bool,
+    /// Number of earlier messages from the same callsite suppressed by rate limiting.
+    pub suppressed: usize,
+}
+
+/// Format of output lines. Set with [set_output_format].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable text, as in `debug: round: i = 3`. This is the default.
+    Text,
+    /// One JSON object per line, as in
+    /// `{"level":"debug","msg":"round","fields":{"i":"3"},"module":"demo","file":"demo.rs",...}`.
+    ///
+    /// Values are written as strings of their [std::fmt::Debug] output: positional ones in a
+    /// `values` array and named ones in a `fields` object. The source location is always
+    /// included; the thread and timestamp (as `ts`, or `elapsed` in seconds) are included when
+    /// enabled.
+    Json,
+    /// logfmt `key=value` pairs, as in `level=debug msg=round i=3 file=demo.rs line=9`.
+    ///
+    /// Values are written as their [std::fmt::Debug] output, quoted and escaped if necessary.
+    /// Named values use their name as the key and positional ones `arg0`, `arg1` and so on. The
+    /// thread and timestamp (as `ts`, or `elapsed` in seconds) are included when enabled.
+    Logfmt,
+}
+
+/// Current output format, as `format as usize`.
+static OUTPUT_FORMAT: AtomicUsize = AtomicUsize::new(OutputFormat::Text as usize);
+
+/// Choose the format of lines written by the writing and per-level macros.
+pub fn set_output_format(format: OutputFormat) {
+    OUTPUT_FORMAT.store(format as usize, SeqCst);
+}
+
+/// Report the current output format.
+pub fn output_format() -> OutputFormat {
+    match OUTPUT_FORMAT.load(SeqCst) {
+        1 => OutputFormat::Json,
+        2 => OutputFormat::Logfmt,
+        _ => OutputFormat::Text,
+    }
+}
+
+/// Indentation of the continuation lines of a multi-line message.
+const CONTINUATION_INDENT: &str = "    ";
+
+/// Whether to show the source location of each message.
+static LOCATION: AtomicBool = AtomicBool::new(false);
+
+/// Show or hide the source location of each message. When shown, it follows the level in the
+/// line prefix, as in `debug[src/parse.rs:42:9]: msg: ...`. It is hidden by default.
+pub fn set_location(location: bool) {
+    LOCATION.store(location, SeqCst);
+}
+
+/// Report whether source locations are shown.
+pub fn is_location() -> bool {
+    LOCATION.load(SeqCst)
+}
+
+/// Whether to pretty-print values.
+static PRETTY: AtomicBool = AtomicBool::new(false);
+
+/// Pretty-print values with `{:#?}` rather than `{:?}`. The continuation lines of multi-line
+/// messages are indented, so that each message still reads as a single record. This is off by
+/// default; see also [debug_pretty](crate::debug_pretty).
+pub fn set_pretty(pretty: bool) {
+    PRETTY.store(pretty, SeqCst);
+}
+
+/// Report whether values are pretty-printed.
+pub fn is_pretty() -> bool {
+    PRETTY.load(SeqCst)
+}
+
+/// Whether to show the current thread of each message.
+static THREAD: AtomicBool = AtomicBool::new(false);
+
+/// Show or hide the thread writing each message: its name, or its [std::thread::ThreadId] if
+/// unnamed. When shown, it follows the level in the line prefix and precedes any source
+/// location, as in `debug[worker-2 src/parse.rs:42:9]: msg: ...`. It is hidden by default.
+pub fn set_thread(thread: bool) {
+    THREAD.store(thread, SeqCst);
+}
+
+/// Report whether threads are shown.
+pub fn is_thread() -> bool {
+    THREAD.load(SeqCst)
+}
+
+/// Write the name or else the ID of the current thread to `out`.
+pub(crate) fn write_thread(out: &mut String) {
+    let thread = std::thread::current();
+    match thread.name() {
+        Some(name) => out.push_str(name),
+        None => write!(out, "{:?}", thread.id()).unwrap(),
+    }
+}
+
+/// Format `record` into a line of output in the current [OutputFormat], without a trailing
+/// newline.
+#[doc(hidden)]
+pub fn __format(record: &Record) -> String {
+    match output_format() {
+        OutputFormat::Text => format_text(record),
+        OutputFormat::Json => crate::json::format_json(record),
+        OutputFormat::Logfmt => crate::logfmt::format_logfmt(record),
+    }
+}
+
+/// `line` without its leading timestamp, if any, for comparing lines.
+pub(crate) fn strip_timestamp(line: &str) -> &str {
+    // Timestamps contain no spaces or commas, and are followed by a space in text and logfmt
+    // and a comma in JSON, where the timestamp is the first member.
+    let separator = match output_format() {
+        OutputFormat::Text | OutputFormat::Logfmt => ' ',
+        OutputFormat::Json => ',',
+    };
+    match timestamps() {
+        Timestamps::None => line,
+        _ => line.split_once(separator).map_or(line, |(_, rest)| rest),
+    }
+}
+
+/// Format `record` as [OutputFormat::Text], indented for the active
+/// [debug_scope](crate::debug_scope)s.
+fn format_text(record: &Record) -> String {
+    let mut line = String::new();
+    crate::time::write_timestamp(&mut line);
+    let indent = crate::scope::indent();
+    line.push_str(&indent);
+    write!(line, "{}", record.level).unwrap();
+    let (thread, location) = (is_thread(), is_location() && !record.file.is_empty());
+    if thread || location {
+        line.push('[');
+        if thread {
+            write_thread(&mut line);
+        }
+        if thread && location {
+            line.push(' ');
+        }
+        if location {
+            write!(line, "{}:{}", record.file, record.line).unwrap();
+            if record.column > 0 {
+                write!(line, ":{}", record.column).unwrap();
+            }
+        }
+        line.push(']');
+    }
+    if record.msg.is_some() || !record.fields.is_empty() {
+        line.push_str(": ");
+    }
+    write_body(&mut line, record);
+    if line.contains('\n') {
+        line = line.replace('\n', &format!("\n{}{}", indent, CONTINUATION_INDENT));
+    }
+    line
+}
+
+/// Write the message and values of `record` as in [OutputFormat::Text], as in
+/// `msg: a = 1, 2 (3 suppressed)`, to `out`.
+pub(crate) fn write_body(out: &mut String, record: &Record) {
+    let pretty = record.pretty || is_pretty();
+    if let Some(msg) = record.msg {
+        write!(out, "{}", msg).unwrap();
+    }
+    for (i, (name, value)) in record.fields.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        } else if record.msg.is_some() {
+            out.push_str(": ");
+        }
+        if let Some(name) = name {
+            write!(out, "{} = ", name).unwrap();
+        }
+        if pretty {
+            write!(out, "{:#?}", value).unwrap();
+        } else {
+            write!(out, "{:?}", value).unwrap();
+        }
+    }
+    if record.suppressed > 0 {
+        write!(out, " ({} suppressed)", record.suppressed).unwrap();
+    }
+}
+
+#[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
+fn test_location() {
+    use crate::{debug_writeln, set_debug};
+    let _lock = crate::test_lock();
+    set_debug(true);
+    set_location(true);
+    let mut msg = String::new();
+    let line = line!() + 1;
+    debug_writeln!(&mut msg, "here", 1);
+    debug_writeln!(&mut msg);
+    set_location(false);
+    let expected = format!(
+        "debug[{0}:{1}:5]: here: 1\ndebug[{0}:{2}:5]\n",
+        file!(),
+        line,
+        line + 1,
+    );
+    assert_eq!(expected, msg);
+}
+
+#[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
+fn test_thread() {
+    use crate::{debug_writeln, set_debug};
+    let _lock = crate::test_lock();
+    set_debug(true);
+    set_thread(true);
+    let msg = std::thread::Builder::new()
+        .name("worker-2".to_string())
+        .spawn(|| {
+            let mut msg = String::new();
+            debug_writeln!(&mut msg, "working");
+            msg
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+    set_thread(false);
+    assert_eq!("debug[worker-2]: working\n", msg);
+}
+
+#[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
+fn test_pretty() {
+    use crate::{debug_writeln, set_debug};
+    let _lock = crate::test_lock();
+    set_debug(true);
+    set_pretty(true);
+    let mut msg = String::new();
+    debug_writeln!(&mut msg, "at", v = vec![1, 2], 3);
+    set_pretty(false);
+    let expected = "debug: at: v = [\n        1,\n        2,\n    ], 3\n";
+    assert_eq!(expected, msg);
+}
@@ -0,0 +1,118 @@
+//! JSON Lines output.
+
+use crate::format::{is_thread, write_thread, Record};
+use crate::time::{elapsed, timestamps, write_rfc3339, Timestamps};
+
+use std::fmt::Write;
+use std::time::SystemTime;
+
+/// Write `s` to `out` as a JSON string.
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Format `record` as [OutputFormat::Json](crate::OutputFormat::Json).
+pub(crate) fn format_json(record: &Record) -> String {
+    let mut out = String::from("{");
+    match timestamps() {
+        Timestamps::None => (),
+        Timestamps::Rfc3339 => {
+            out.push_str("\"ts\":\"");
+            write_rfc3339(&mut out, SystemTime::now());
+            out.push_str("\",");
+        }
+        Timestamps::Elapsed => {
+            let elapsed = elapsed();
+            let (secs, micros) = (elapsed.as_secs(), elapsed.subsec_micros());
+            write!(out, "\"elapsed\":{}.{:06},", secs, micros).unwrap();
+        }
+    }
+    write!(out, "\"level\":\"{}\"", record.level).unwrap();
+    if let Some(msg) = record.msg {
+        out.push_str(",\"msg\":");
+        write_string(&mut out, &msg.to_string());
+    }
+    let values = record.fields.iter().filter(|(name, _)| name.is_none());
+    for (i, (_, value)) in values.enumerate() {
+        out.push_str(if i == 0 { ",\"values\":[" } else { "," });
+        write_string(&mut out, &format!("{:?}", value));
+    }
+    if record.fields.iter().any(|(name, _)| name.is_none()) {
+        out.push(']');
+    }
+    let fields = record
+        .fields
+        .iter()
+        .filter_map(|(name, value)| Some((name.as_ref()?, value)));
+    for (i, (name, value)) in fields.enumerate() {
+        out.push_str(if i == 0 { ",\"fields\":{" } else { "," });
+        write_string(&mut out, name);
+        out.push(':');
+        write_string(&mut out, &format!("{:?}", value));
+    }
+    if record.fields.iter().any(|(name, _)| name.is_some()) {
+        out.push('}');
+    }
+    out.push_str(",\"module\":");
+    write_string(&mut out, record.module_path);
+    if !record.file.is_empty() {
+        out.push_str(",\"file\":");
+        write_string(&mut out, record.file);
+        write!(out, ",\"line\":{}", record.line).unwrap();
+    }
+    if record.column > 0 {
+        write!(out, ",\"column\":{}", record.column).unwrap();
+    }
+    if is_thread() {
+        let mut thread = String::new();
+        write_thread(&mut thread);
+        out.push_str(",\"thread\":");
+        write_string(&mut out, &thread);
+    }
+    if record.suppressed > 0 {
+        write!(out, ",\"suppressed\":{}", record.suppressed).unwrap();
+    }
+    out.push('}');
+    out
+}
+
+#[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
+fn test_json() {
+    use crate::{debug_writeln, set_debug, set_output_format, OutputFormat};
+    let _lock = crate::test_lock();
+    set_debug(true);
+    set_output_format(OutputFormat::Json);
+    let (i, s) = (3, "a \"b\",\n\tc");
+    let mut msg = String::new();
+    let line = line!() + 1;
+    debug_writeln!(&mut msg, "round", i, s, 'x', 1 + 1);
+    debug_writeln!(&mut msg);
+    set_output_format(OutputFormat::Text);
+    let expected = format!(
+        concat!(
+            r#"{{"level":"debug","msg":"round","values":["'x'","2"],"#,
+            r#""fields":{{"i":"3","s":"\"a \\\"b\\\",\\n\\tc\""}},"#,
+            r#""module":"debug_macros::json","file":"{0}","line":{1},"column":5}}"#,
+            "\n",
+            r#"{{"level":"debug","module":"debug_macros::json","file":"{0}","line":{2},"column":5}}"#,
+            "\n",
+        ),
+        file!(),
+        line,
+        line + 1,
+    );
+    assert_eq!(expected, msg);
+}
@@ -0,0 +1,77 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Severity of a debug message, from most to least severe.
+///
+/// Each level prefixes its output lines with its lowercase name, so that a
+/// message at [Level::Warn] is printed as `warn: ...`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    /// Lowercase name of this level, as used in line prefixes.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        }
+    }
+
+    /// Inverse of `level as usize`, with `0` meaning "off".
+    pub(crate) fn from_usize(n: usize) -> Option<Level> {
+        match n {
+            1 => Some(Level::Error),
+            2 => Some(Level::Warn),
+            3 => Some(Level::Info),
+            4 => Some(Level::Debug),
+            5 => Some(Level::Trace),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Error returned when parsing a [Level] from an unknown name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseLevelError;
+
+impl fmt::Display for ParseLevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("unknown debug level name")
+    }
+}
+
+impl std::error::Error for ParseLevelError {}
+
+impl FromStr for Level {
+    type Err = ParseLevelError;
+
+    /// Parse a level from its name, ignoring case.
+    fn from_str(s: &str) -> Result<Level, ParseLevelError> {
+        [
+            Level::Error,
+            Level::Warn,
+            Level::Info,
+            Level::Debug,
+            Level::Trace,
+        ]
+        .iter()
+        .copied()
+        .find(|l| l.as_str().eq_ignore_ascii_case(s))
+        .ok_or(ParseLevelError)
+    }
+}
@@ -1,8 +1,88 @@
 //! Debugging macros. These macros allow adding suppressable debug
 //! prints to code.
 //!
+//! Messages are reported at one of several severity [Level]s, via the [error], [warn], [info],
+//! [debug] and [trace] macros. Only messages at or above the current maximum level (see
+//! [set_level]) are printed. The level can also be set per module with [set_filter], and
+//! debugging can be turned on or off temporarily, globally or per thread, with [scoped] and
+//! [scoped_thread], or for the current thread with [set_thread_debug].
+//!
+//! Lines can be annotated with their thread with [set_thread] and their source location with
+//! [set_location], and prefixed with a timestamp with [set_timestamps]. They can also be
+//! written as JSON Lines or logfmt for machine consumption with [set_output_format].
+//!
 //! To enable debugging prints, compile this crate with the `debug_emit` feature enabled.  See
-//! [set_debug] for details.
+//! [set_debug] for details. To remove them entirely instead, compile it with the
+//! `debug_static` feature and without `debug_emit`: the macros then evaluate nothing (though
+//! their arguments are still type-checked), and optimized builds contain no trace of them.
+//! Debugging can also be configured at startup from the `DEBUG_MACROS` environment variable:
+//! see [init_from_env].
+//!
+//! The per-level macros write to [std::io::stderr] by default; [set_sink] redirects their output
+//! to any other [Sink]. Runs of repeated lines can be collapsed with [set_dedup]. In tests,
+//! [capture] collects the output of the current thread.
+//!
+//! With the `log` feature, the per-level macros forward their messages to the `log` crate when
+//! another logger is installed, with the module path as the target. Conversely, `init_log`
+//! installs a `Logger` that reports records from `log`, such as those of dependencies, through
+//! this crate's levels, filters and formatting.
+//!
+//! With the `tracing` feature, the per-level macros likewise emit `tracing` events when a
+//! subscriber is installed, and write to the [Sink] as usual otherwise. A `TracingLayer` added
+//! to a `tracing_subscriber` registry renders `tracing` events in this crate's format.
+//!
+//! The [trace_fn] attribute reports entry to and exit from a function, with its arguments,
+//! return value and running time; it is not named `trace`, which would clash with the [trace]
+//! macro. Within a [debug_scope], text output on the current thread is indented, which shows the
+//! nesting of recursive code.
+
+mod callsite;
+mod capture;
+mod dedup;
+mod env;
+mod filter;
+mod format;
+mod json;
+mod level;
+#[cfg(feature = "log")]
+mod log_bridge;
+mod logfmt;
+mod overrides;
+mod policy;
+mod rate;
+mod scope;
+mod sink;
+mod time;
+#[cfg(feature = "tracing")]
+mod tracing_bridge;
+
+pub use callsite::{Callsite, EventSite};
+pub use capture::{capture, Capture};
+pub use dedup::{dedup, flush_dedup, set_dedup};
+pub use env::{init_from_env, ENV_VAR};
+pub use filter::{clear_filter, set_filter, FilterError};
+pub use format::{
+    __format, is_location, is_pretty, is_thread, output_format, set_location, set_output_format,
+    set_pretty, set_thread, OutputFormat, Record,
+};
+pub use level::{Level, ParseLevelError};
+#[cfg(feature = "log")]
+pub use log_bridge::{init_log, Logger};
+pub use overrides::{
+    inherit, scoped, scoped_thread, set_thread_debug, spawn, thread_debug, ScopedDebug,
+    ScopedThreadDebug,
+};
+pub use policy::{
+    __write_failed, set_write_error_policy, write_error_policy, write_errors, WriteErrorPolicy,
+};
+pub use rate::RateLimit;
+pub use scope::{is_scope_lines, set_scope_lines, Scope};
+pub use sink::{reset_sink, set_sink, MemorySink, Sink, StderrSink, WriterSink};
+pub use time::{set_timestamps, timestamps, Timestamps};
+#[cfg(feature = "tracing")]
+pub use tracing_bridge::TracingLayer;
+
+pub use debug_macros_trace::trace_fn;
 
 #[doc(hidden)]
 pub use std::io::stderr;
@@ -14,30 +94,345 @@ pub use std::{write, writeln};
 /// Rename of [std::io::Write] as a convenience.
 pub use std::io::Write as WriteIO;
 
-static DEBUG: AtomicBool =
-    AtomicBool::new(cfg!(feature="debug_emit") && (cfg!(debug_assertions) || cfg!(test)));
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+/// Most verbose level currently reported, as `level as usize`; `0` is off.
+static LEVEL: AtomicUsize = AtomicUsize::new(
+    if cfg!(feature = "debug_emit") && (cfg!(debug_assertions) || cfg!(test)) {
+        Level::Debug as usize
+    } else {
+        0
+    },
+);
+
+/// Set the most verbose [Level] that will be reported, or `None` to turn all output off.
+///
+/// The default is as for [set_debug].
+pub fn set_level(level: Option<Level>) {
+    env::lazy_init();
+    store_level(level);
+}
+
+/// Implementation of [set_level], without triggering lazy initialization.
+fn store_level(level: Option<Level>) {
+    LEVEL.store(level.map_or(0, |l| l as usize), Relaxed);
+    callsite::invalidate();
+}
+
+/// Report the most verbose [Level] currently reported, if any.
+pub fn level() -> Option<Level> {
+    env::lazy_init();
+    Level::from_usize(LEVEL.load(Relaxed))
+}
+
+/// False when the macros are compiled out, which is when this crate is built with the
+/// `debug_static` feature but without `debug_emit`.
+#[doc(hidden)]
+pub const __STATIC_ENABLED: bool =
+    !cfg!(all(feature = "debug_static", not(feature = "debug_emit")));
+
+/// Report whether messages at `level` are currently reported, ignoring module filters.
+pub fn is_enabled(level: Level) -> bool {
+    if let Some(max) = overrides::thread_level() {
+        return __STATIC_ENABLED && max.is_some_and(|max| level <= max);
+    }
+    env::lazy_init();
+    __STATIC_ENABLED && level as usize <= LEVEL.load(Relaxed)
+}
+
+/// Report whether messages at `level` from the module at `module_path` are currently reported.
+/// The macros check this with their caller's [std::module_path].
+///
+/// The longest matching filter set with [set_filter] applies; if none matches, this is the
+/// same as [is_enabled].
+pub fn is_module_enabled(level: Level, module_path: &str) -> bool {
+    module_level(module_path).is_some_and(|max| level <= max)
+}
+
+/// Report the most verbose [Level] currently reported from the module at `module_path`, if
+/// any. See [is_module_enabled].
+pub fn module_level(module_path: &str) -> Option<Level> {
+    if !__STATIC_ENABLED {
+        return None;
+    }
+    if let Some(max) = overrides::thread_level() {
+        return max;
+    }
+    match filter::lookup(module_path) {
+        Some(max) => max,
+        None => level(),
+    }
+}
 
-/// Force debugging on or off.
+/// Force debugging on or off. This is shorthand for setting the maximum level to [Level::Debug]
+/// or turning all output off with [set_level].
 ///
 /// Debugging will be on by default when this crate is compiled with its `debug_emit` feature
-/// enabled, *and also* `debug_assertions` or `test` configured. Otherwise/// debugging will be
+/// enabled, *and also* `debug_assertions` or `test` configured. Otherwise debugging will be
 /// off by default.
 pub fn set_debug(debug: bool) {
-    DEBUG.store(debug, SeqCst);
+    set_level(if debug { Some(Level::Debug) } else { None });
 }
 
-/// Report current debugging status.
+/// Report whether messages at [Level::Debug] are currently reported.
 pub fn is_debug() -> bool {
-    DEBUG.load(SeqCst)
+    is_enabled(Level::Debug)
+}
+
+/// Check whether messages at `level` from the caller's module are currently reported, using
+/// a [Callsite] cache. The compile-time check comes first, so that when the macros are
+/// compiled out the code guarded by this is still type-checked but is dead and is removed by
+/// the optimizer.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __enabled {
+    ($level:expr) => {{
+        static CALLSITE: $crate::Callsite = $crate::Callsite::new();
+        $crate::__STATIC_ENABLED && CALLSITE.is_enabled($level, module_path!())
+    }};
+}
+
+/// Write `line`, a message at `level` from the module at `module_path`, to the installed
+/// [Sink], handling failure according to the current [WriteErrorPolicy].
+#[doc(hidden)]
+pub fn __emit(level: Level, module_path: &str, line: &str) {
+    if let Err(e) = sink::emit(level, module_path, line) {
+        __write_failed(e, line);
+    }
+}
+
+/// Report `record`: with the `log` feature, to the `log` crate if a logger other than
+/// [Logger] is installed; with the `tracing` feature, as a `tracing` event if a subscriber is
+/// installed; and otherwise formatted to the installed [Sink] by [__emit]. Called by the
+/// per-level macros, each invocation of which passes its own `site`.
+#[doc(hidden)]
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub fn __emit_record(record: &Record, site: &'static EventSite) {
+    #[cfg(feature = "log")]
+    {
+        if log_bridge::forward(record) {
+            return;
+        }
+    }
+    #[cfg(feature = "tracing")]
+    {
+        if tracing_bridge::forward(record, site) {
+            return;
+        }
+    }
+    __emit(record.level, record.module_path, &__format(record));
+}
+
+/// Report a message from another logging system through this crate's level and filter checks,
+/// formatting and [Sink], as if it came from one of the per-level macros. `target` takes the
+/// place of the module path, and `location` is the source file and line, if known.
+///
+/// This is the entry point for adapters from other logging facades, such as the `Logger` of
+/// the `log` feature.
+///
+/// See [Sink::write_record] for the other direction.
+///
+/// # Examples
+///
+/// ```
+/// # if !debug_macros::__STATIC_ENABLED { return; }
+/// use debug_macros::{report, Level};
+/// debug_macros::set_debug(true);
+/// let cap = debug_macros::capture();
+/// report(Level::Warn, "hyper::client", None, format_args!("retrying {}", 2));
+/// report(Level::Trace, "hyper::client", None, format_args!("not shown"));
+/// assert_eq!(cap.lines(), vec!["warn: retrying 2"]);
+/// ```
+pub fn report(level: Level, target: &str, location: Option<(&str, u32)>, args: fmt::Arguments) {
+    report_fields(level, target, location, Some(args), &[]);
+}
+
+/// As [report], but with an optional message and values, named or positional, as for [debug].
+///
+/// This lets adapters from structured systems keep their fields, as the `TracingLayer` of the
+/// `tracing` feature does.
+///
+/// # Examples
+///
+/// ```
+/// # if !debug_macros::__STATIC_ENABLED { return; }
+/// use debug_macros::{report_fields, Level};
+/// debug_macros::set_debug(true);
+/// let cap = debug_macros::capture();
+/// let fields: &[(Option<&'static str>, &dyn std::fmt::Debug)] = &[(Some("id"), &7), (None, &"x")];
+/// report_fields(Level::Info, "server", None, Some(format_args!("accepted")), fields);
+/// assert_eq!(cap.lines(), vec![r#"info: accepted: id = 7, "x""#]);
+/// ```
+pub fn report_fields(
+    level: Level,
+    target: &str,
+    location: Option<(&str, u32)>,
+    msg: Option<fmt::Arguments>,
+    fields: &[(Option<&'static str>, &dyn fmt::Debug)],
+) {
+    if !is_module_enabled(level, target) {
+        return;
+    }
+    let (file, line) = location.unwrap_or(("", 0));
+    let record = Record {
+        level,
+        module_path: target,
+        file,
+        line,
+        column: 0,
+        msg,
+        fields,
+        pretty: false,
+        suppressed: 0,
+    };
+    __emit(level, target, &__format(&record));
+}
+
+/// Format the text of a message into a new [String], without a trailing newline, or with
+/// `emit` as the last element of the head, report it with [__emit_record]. Used to implement the
+/// writing and per-level macros.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __format_message {
+    (@record [$level:expr, $pretty:expr, $suppressed:expr, emit], $msg:expr, [$($field:tt)*]) => {{
+        static SITE: $crate::EventSite = $crate::EventSite::new();
+        $crate::__emit_record(
+            &$crate::Record {
+                level: $level,
+                module_path: module_path!(),
+                file: file!(),
+                line: line!(),
+                column: column!(),
+                msg: $msg,
+                pretty: $pretty,
+                suppressed: $suppressed,
+                fields: &[$($field)*],
+            },
+            &SITE,
+        )
+    }};
+    (@record [$level:expr, $pretty:expr, $suppressed:expr], $msg:expr, [$($field:tt)*]) => {
+        $crate::__format(&$crate::Record {
+            level: $level,
+            module_path: module_path!(),
+            file: file!(),
+            line: line!(),
+            column: column!(),
+            msg: $msg,
+            pretty: $pretty,
+            suppressed: $suppressed,
+            fields: &[$($field)*],
+        })
+    };
+    (@fields $head:tt, $msg:expr, [$($field:tt)*] $name:ident = $e:expr $(, $($rest:tt)*)?) => {
+        $crate::__format_message!(
+            @fields $head, $msg, [$($field)* (Some(stringify!($name)), &$e),] $($($rest)*)?
+        )
+    };
+    // `true` and `false` are identifiers to macros, but are values, not variables.
+    (@fields $head:tt, $msg:expr, [$($field:tt)*] true $(, $($rest:tt)*)?) => {
+        $crate::__format_message!(@fields $head, $msg, [$($field)* (None, &true),] $($($rest)*)?)
+    };
+    (@fields $head:tt, $msg:expr, [$($field:tt)*] false $(, $($rest:tt)*)?) => {
+        $crate::__format_message!(@fields $head, $msg, [$($field)* (None, &false),] $($($rest)*)?)
+    };
+    (@fields $head:tt, $msg:expr, [$($field:tt)*] $name:ident $(, $($rest:tt)*)?) => {
+        $crate::__format_message!(
+            @fields $head, $msg, [$($field)* (Some(stringify!($name)), &$name),] $($($rest)*)?
+        )
+    };
+    (@fields $head:tt, $msg:expr, [$($field:tt)*] $e:expr $(, $($rest:tt)*)?) => {
+        $crate::__format_message!(@fields $head, $msg, [$($field)* (None, &$e),] $($($rest)*)?)
+    };
+    (@fields $head:tt, $msg:expr, [$($field:tt)*]) => {
+        $crate::__format_message!(@record $head, $msg, [$($field)*])
+    };
+    ($head:tt, fmt = $fmt:literal $(, $($args:tt)*)?) => {
+        $crate::__format_message!(@record $head, Some(format_args!($fmt $(, $($args)*)?)), [])
+    };
+    ($head:tt, $msg:literal, $($args:tt)+) => {
+        $crate::__format_message!(@fields $head, Some(format_args!("{}", $msg)), [] $($args)+)
+    };
+    ($head:tt, $msg:literal $(,)?) => {
+        $crate::__format_message!(@record $head, Some(format_args!("{}", $msg)), [])
+    };
+    ($head:tt $(,)?) => {
+        $crate::__format_message!(@record $head, None, [])
+    };
+}
+
+/// Write a message at the given [Level] to a formatter ala [std::writeln]. The formatter must
+/// have a `write_fmt` method: generally this is either [std::fmt::Write] or [std::io::Write].
+///
+/// # Examples
+///
+/// ```
+/// # if !debug_macros::__STATIC_ENABLED { return; }
+/// use debug_macros::{level_writeln, Level};
+/// use std::fmt::Write; // For writing a String.
+/// let mut logger = String::new();
+/// debug_macros::set_level(Some(Level::Info));
+/// level_writeln!(&mut logger, Level::Warn, "low fuel", 3);
+/// level_writeln!(&mut logger, Level::Debug, "not shown");
+/// assert_eq!(logger, "warn: low fuel: 3\n");
+/// ```
+///
+/// The message is written with a single call to `write_fmt`. If that fails, the current
+/// [WriteErrorPolicy] applies; see [try_level_writeln] to handle the error instead.
+///
+/// # Panics
+///
+/// Panics if a write fails under the default [WriteErrorPolicy::Panic].
+#[macro_export]
+macro_rules! level_writeln {
+    ($f:expr, $level:expr $(, $($args:tt)*)?) => {{
+        let level: $crate::Level = $level;
+        if $crate::__enabled!(level) {
+            let line = $crate::__format_message!([level, false, 0], $($($args)*)?);
+            if let Err(e) = $crate::writeln!($f, "{}", line) {
+                $crate::__write_failed(e, &line);
+            }
+        }
+    }};
+}
+
+/// As [level_writeln], but returns the result of the write, `Ok(())` if nothing was written:
+/// either [std::io::Result] or [std::fmt::Result], depending on the formatter.
+///
+/// # Examples
+///
+/// ```
+/// # if !debug_macros::__STATIC_ENABLED { return; }
+/// use debug_macros::{try_level_writeln, Level};
+/// use std::io::Write;
+/// let mut logger = Vec::new();
+/// debug_macros::set_debug(true);
+/// try_level_writeln!(&mut logger, Level::Info, "progress", 1).unwrap();
+/// assert_eq!(logger, b"info: progress: 1\n");
+/// ```
+#[macro_export]
+macro_rules! try_level_writeln {
+    ($f:expr, $level:expr $(, $($args:tt)*)?) => {{
+        let level: $crate::Level = $level;
+        if $crate::__enabled!(level) {
+            let line = $crate::__format_message!([level, false, 0], $($($args)*)?);
+            $crate::writeln!($f, "{}", line)
+        } else {
+            Ok(())
+        }
+    }};
 }
 
 /// Write a message to a formatter ala [std::writeln]. The formatter
 /// must have a `write_fmt` method: generally this is either [std::fmt::Write] or
-/// [std::io::Write].
+/// [std::io::Write]. This is [level_writeln] at [Level::Debug]. The message and values are as
+/// for [debug], including named values.
 ///
 /// # Examples
 ///
 /// ```
+/// # if !debug_macros::__STATIC_ENABLED { return; }
 /// use debug_macros::debug_writeln;
 /// use std::fmt::Write; // For writing a String.
 /// let mut logger = String::new();
@@ -48,53 +443,363 @@ pub fn is_debug() -> bool {
 ///
 /// # Panics
 ///
-/// Panics if a write fails.
+/// Panics if a write fails under the default [WriteErrorPolicy::Panic].
 #[macro_export]
 macro_rules! debug_writeln {
-    ($f:expr, $msg:literal, $x0:expr $(, $xs:expr)* $(,)?) => {
-        if $crate::is_debug() {
-            $crate::write!($f, "debug: {}: ", $msg).unwrap();
-            $crate::write!($f, "{:?}", $x0).unwrap();
-            $($crate::write!($f, ", {:?}", $xs).unwrap();)*
-            $crate::writeln!($f).unwrap();
+    ($f:expr $(, $($args:tt)*)?) => {
+        $crate::level_writeln!($f, $crate::Level::Debug, $($($args)*)?)
+    };
+}
+
+/// As [debug_writeln], but returns the result of the write. See [try_level_writeln].
+#[macro_export]
+macro_rules! try_debug_writeln {
+    ($f:expr $(, $($args:tt)*)?) => {
+        $crate::try_level_writeln!($f, $crate::Level::Debug, $($($args)*)?)
+    };
+}
+
+/// Formats a message and writes it as a single line to the installed [Sink] (by default a
+/// locked [std::io::stderr], so that output occurs consecutively), or forwards it as described
+/// at [__emit_record]. Used to implement the per-level macros.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log {
+    ([$level:expr, $pretty:expr], $($args:tt)*) => {{
+        let level: $crate::Level = $level;
+        if $crate::__enabled!(level) {
+            $crate::__format_message!([level, $pretty, 0, emit], $($args)*);
+        }
+    }};
+}
+
+/// As [__log] at [Level::Debug], but emitting only when `$check`, an expression on the
+/// callsite's [RateLimit] bound to `$limit`, returns the number of calls suppressed since the
+/// last emission. Used to implement the rate-limited macros.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_limited {
+    ($limit:ident => $check:expr; $($args:tt)*) => {{
+        static LIMIT: $crate::RateLimit = $crate::RateLimit::new();
+        let level = $crate::Level::Debug;
+        if $crate::__enabled!(level) {
+            let $limit = &LIMIT;
+            if let Some(suppressed) = $check {
+                $crate::__format_message!([level, false, suppressed, emit], $($args)*);
+            }
         }
+    }};
+}
+
+/// As [debug], but reports only the first of every `n` calls made while debugging is enabled.
+/// Each report after the first notes how many calls were suppressed since the last one. `n` may
+/// be of any integer type.
+///
+/// # Examples
+///
+/// ```
+/// # if !debug_macros::__STATIC_ENABLED { return; }
+/// use debug_macros::debug_every;
+/// debug_macros::set_debug(true);
+/// let cap = debug_macros::capture();
+/// let batch: usize = 3;
+/// for i in 0..7 {
+///     debug_every!(batch, "round", i);
+/// }
+/// assert_eq!(
+///     cap.lines(),
+///     vec![
+///         "debug: round: i = 0",
+///         "debug: round: i = 3 (2 suppressed)",
+///         "debug: round: i = 6 (2 suppressed)",
+///     ],
+/// );
+/// ```
+#[macro_export]
+macro_rules! debug_every {
+    ($n:expr, $($args:tt)*) => {
+        $crate::__log_limited!(limit => limit.every($n as u64); $($args)*)
+    };
+}
+
+/// As [debug], but reports only the first call made while debugging is enabled.
+///
+/// # Examples
+///
+/// ```
+/// # if !debug_macros::__STATIC_ENABLED { return; }
+/// use debug_macros::debug_once;
+/// debug_macros::set_debug(true);
+/// let cap = debug_macros::capture();
+/// for i in 0..3 {
+///     debug_once!("starting", i);
+/// }
+/// assert_eq!(cap.lines(), vec!["debug: starting: i = 0"]);
+/// ```
+#[macro_export]
+macro_rules! debug_once {
+    ($($args:tt)*) => {
+        $crate::__log_limited!(limit => limit.once(); $($args)*)
+    };
+}
+
+/// As [debug], but reports at most `per_sec` calls per second, suppressing calls that come
+/// too soon after the last one reported. Each report notes how many calls were suppressed
+/// since the last one. `per_sec` may be an integer or a float, such as `0.1` for one call
+/// every ten seconds.
+///
+/// # Examples
+///
+/// ```
+/// # if !debug_macros::__STATIC_ENABLED { return; }
+/// use debug_macros::debug_rate;
+/// debug_macros::set_debug(true);
+/// let cap = debug_macros::capture();
+/// for i in 0..3 {
+///     debug_rate!(1, "tick", i);
+/// }
+/// debug_rate!(0.5, "tock");
+/// assert_eq!(cap.lines(), vec!["debug: tick: i = 0", "debug: tock"]);
+/// ```
+#[macro_export]
+macro_rules! debug_rate {
+    ($per_sec:expr, $($args:tt)*) => {
+        $crate::__log_limited!(limit => limit.rate($per_sec as f64); $($args)*)
     };
-    ($f:expr, $msg:literal) => {
-        if $crate::is_debug() {
-            $crate::writeln!($f, "debug: {}", $msg).unwrap();
+}
+
+/// Report a message at [Level::Error] to the installed [Sink]. Takes the same arguments as
+/// [debug].
+#[macro_export]
+macro_rules! error {
+    ($($args:tt)*) => { $crate::__log!([$crate::Level::Error, false], $($args)*) };
+}
+
+/// Report a message at [Level::Warn] to the installed [Sink]. Takes the same arguments as
+/// [debug].
+#[macro_export]
+macro_rules! warn {
+    ($($args:tt)*) => { $crate::__log!([$crate::Level::Warn, false], $($args)*) };
+}
+
+/// Report a message at [Level::Info] to the installed [Sink]. Takes the same arguments as
+/// [debug].
+#[macro_export]
+macro_rules! info {
+    ($($args:tt)*) => { $crate::__log!([$crate::Level::Info, false], $($args)*) };
+}
+
+/// Report a message at [Level::Debug] to the installed [Sink], by default [std::io::stderr]
+/// (locked, so that debug output occurs consecutively). The output is as for [debug_writeln].
+///
+/// The message is a literal, optionally followed by values to print with [std::fmt::Debug].
+/// A value may be named as `name = expr`, and a value that is just a variable `x` is named
+/// `x`, so that it is printed as `x = value` in the manner of [std::dbg].
+///
+/// Alternatively, the message may be given as `fmt = "..."` followed by arguments, in which
+/// case it is formatted as by [std::format] and printed without separate values.
+///
+/// # Examples
+///
+/// ```
+/// # if !debug_macros::__STATIC_ENABLED { return; }
+/// use debug_macros::debug;
+/// debug_macros::set_debug(true);
+/// let cap = debug_macros::capture();
+/// let (i, sum) = (3, 6);
+/// debug!("round", i, total = sum, sum + 1);
+/// debug!(fmt = "finished {} rounds, total {sum:04}", i);
+/// assert_eq!(
+///     cap.lines(),
+///     vec!["debug: round: i = 3, total = 6, 7", "debug: finished 3 rounds, total 0006"],
+/// );
+/// ```
+#[macro_export]
+macro_rules! debug {
+    ($($args:tt)*) => { $crate::__log!([$crate::Level::Debug, false], $($args)*) };
+}
+
+/// As [debug], but with values pretty-printed with `{:#?}`, as after [set_pretty].
+///
+/// # Examples
+///
+/// ```
+/// # if !debug_macros::__STATIC_ENABLED { return; }
+/// use debug_macros::debug_pretty;
+/// debug_macros::set_debug(true);
+/// let cap = debug_macros::capture();
+/// debug_pretty!("pair", (1, 2));
+/// assert_eq!(cap.lines(), vec!["debug: pair: (\n        1,\n        2,\n    )"]);
+/// ```
+#[macro_export]
+macro_rules! debug_pretty {
+    ($($args:tt)*) => { $crate::__log!([$crate::Level::Debug, true], $($args)*) };
+}
+
+/// Evaluate an expression, report its source and value at [Level::Debug] as
+/// `debug: expr = value`, and return the value, in the manner of [std::dbg]. Unlike
+/// [std::dbg], nothing is reported unless debugging is enabled.
+///
+/// With several arguments, each is reported in turn and a tuple of their values is returned.
+///
+/// # Examples
+///
+/// ```
+/// # if !debug_macros::__STATIC_ENABLED { return; }
+/// use debug_macros::debug_val;
+/// debug_macros::set_debug(true);
+/// let cap = debug_macros::capture();
+/// let a = 3;
+/// let b = debug_val!(a * 2) + 1;
+/// let (c, d) = debug_val!(b, String::from("x"));
+/// assert_eq!((b, c, d.as_str()), (7, 7, "x"));
+/// assert_eq!(
+///     cap.lines(),
+///     vec!["debug: a * 2 = 6", "debug: b = 7", "debug: String::from(\"x\") = \"x\""],
+/// );
+/// ```
+#[macro_export]
+macro_rules! debug_val {
+    ($val:expr $(,)?) => {
+        match $val {
+            val => {
+                if $crate::__enabled!($crate::Level::Debug) {
+                    $crate::__format_message!(
+                        @record [$crate::Level::Debug, false, 0, emit],
+                        None,
+                        [(Some(stringify!($val)), &val),]
+                    );
+                }
+                val
+            }
         }
     };
-    ($f:expr) => {
-        if $crate::is_debug() {
-            $crate::writeln!($f, "debug").unwrap();
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::debug_val!($val)),+,)
+    };
+}
+
+/// Open a scope named `$name`, a `&'static str`, until the returned [Scope] guard is dropped.
+/// While it is open, text output on the current thread is indented one step further, so that
+/// the output of recursive code such as parsers and tree walks shows its nesting. Entry to and
+/// exit from the scope are also reported if enabled with [set_scope_lines], though the exit is
+/// not if [Level::Debug] has since been disabled for the caller's module. JSON and logfmt output
+/// is not indented.
+///
+/// The scope is opened only if [Level::Debug] is enabled for the caller's module. Bind the guard
+/// to a named variable: `let _ = debug_scope!(...)` drops it, closing the scope, immediately.
+///
+/// # Examples
+///
+/// ```
+/// # if !debug_macros::__STATIC_ENABLED { return; }
+/// use debug_macros::{debug, debug_scope};
+///
+/// fn walk(depth: u32) {
+///     let _scope = debug_scope!("walk");
+///     debug!("visit", depth);
+///     if depth > 0 {
+///         walk(depth - 1);
+///     }
+/// }
+///
+/// debug_macros::set_debug(true);
+/// debug_macros::set_scope_lines(true);
+/// let cap = debug_macros::capture();
+/// walk(1);
+/// assert_eq!(
+///     cap.lines(),
+///     vec![
+///         "debug: enter walk",
+///         "  debug: visit: depth = 1",
+///         "  debug: enter walk",
+///         "    debug: visit: depth = 0",
+///         "  debug: exit walk",
+///         "debug: exit walk",
+///     ],
+/// );
+/// ```
+#[macro_export]
+macro_rules! debug_scope {
+    ($name:expr $(,)?) => {
+        if $crate::__STATIC_ENABLED {
+            static SITES: [$crate::EventSite; 2] =
+                [$crate::EventSite::new(), $crate::EventSite::new()];
+            $crate::Scope::__new(
+                $crate::__enabled!($crate::Level::Debug),
+                $name,
+                module_path!(),
+                (file!(), line!(), column!()),
+                &SITES,
+            )
+        } else {
+            $crate::Scope::__disabled()
         }
     };
 }
 
-/// Calls [debug_writeln] to write to [std::io::stderr] (locked, so that
-/// debug output occurs consecutively).
+/// Report entry to a function named in `$msg`, with its arguments, if [Level::Debug] is
+/// enabled, returning the time of entry if so. Used to implement [trace_fn].
+#[doc(hidden)]
 #[macro_export]
-macro_rules! debug {
-    ($msg:literal, $($e:expr),+ $(,)?) => {{
-        use $crate::WriteIO;
-        let stderr = $crate::stderr();
-        $crate::debug_writeln!(&mut stderr.lock(), $msg, $($e),*);
-    }};
-    ($msg:literal) => {{
-        use $crate::WriteIO;
-        let stderr = $crate::stderr();
-        $crate::debug_writeln!(&mut stderr.lock(), $msg);
-    }};
-    () => {{
-        use $crate::WriteIO;
-        let stderr = $crate::stderr();
-        $crate::debug_writeln!(&mut stderr.lock());
+macro_rules! __trace_enter {
+    ($msg:literal $(, $arg:ident)*) => {{
+        let level = $crate::Level::Debug;
+        if $crate::__enabled!(level) {
+            $crate::__format_message!([level, false, 0, emit], $msg $(, $arg)*);
+            Some(std::time::Instant::now())
+        } else {
+            None
+        }
     }};
 }
 
+/// Report exit from a function named in `$msg` with the value `$ret`, if its entry was
+/// reported at the time `$start`. Used to implement [trace_fn].
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __trace_exit {
+    ($start:expr, $msg:literal, $ret:expr) => {
+        if let Some(start) = $start {
+            let level = $crate::Level::Debug;
+            $crate::__format_message!(
+                [level, false, 0, emit],
+                fmt = "{}: {:?} ({:?})",
+                $msg,
+                $ret,
+                start.elapsed()
+            );
+        }
+    };
+}
+
+/// Call `f`, the body of a function under [trace_fn]. Taking `f` as [FnOnce] lets it return
+/// references to its captures, as the function could.
+#[doc(hidden)]
+#[inline(always)]
+pub fn __trace_call<R>(f: impl FnOnce() -> R) -> R {
+    f()
+}
+
+/// Report a message at [Level::Trace] to the installed [Sink]. Takes the same arguments as
+/// [debug].
+#[macro_export]
+macro_rules! trace {
+    ($($args:tt)*) => { $crate::__log!([$crate::Level::Trace, false], $($args)*) };
+}
+
+/// Serializes tests that change the global debug state.
+#[cfg(test)]
+fn test_lock() -> std::sync::MutexGuard<'static, ()> {
+    static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
 #[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
 pub fn test_debug_writeln() {
     use std::fmt::Write;
+    let _lock = test_lock();
     set_debug(true);
     macro_rules! test_msg {
         ($r:literal, $m:literal $(, $e:expr)* ; $($comma:tt)?) => {{
@@ -116,10 +821,97 @@ pub fn test_debug_writeln() {
     test_msg!();
 }
 
-// XXX This test is currently disabled since it writes on stderr.  There is no good way to capture
-// this output, and it blorts into the `cargo test` output where it is not wanted.
-#[cfg(any())]
 #[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
+fn test_named_fields() {
+    use std::fmt::Write;
+    let _lock = test_lock();
+    set_debug(true);
+    let (x, v) = (3, vec![1, 2]);
+    let mut msg = String::new();
+    debug_writeln!(&mut msg, "vars", x, v.len(), x == 3, y = x + 1, true,);
+    debug_writeln!(&mut msg, "one", n = 5);
+    fn f() -> i32 {
+        2
+    }
+    debug_writeln!(&mut msg, "neg", -x, -f(), false, -1);
+    assert_eq!(
+        "debug: vars: x = 3, 2, true, y = 4, true\ndebug: one: n = 5\ndebug: neg: -3, -2, false, -1\n",
+        msg,
+    );
+}
+
+#[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
+fn test_format_string() {
+    use std::fmt::Write;
+    let _lock = test_lock();
+    set_debug(true);
+    let (done, total) = (3, 10);
+    let mut msg = String::new();
+    debug_writeln!(&mut msg, fmt = "processed {} of {} items", done, total);
+    debug_writeln!(
+        &mut msg,
+        fmt = "{:>4}|{:x}|{:.2}|{w}|{total}",
+        done,
+        255,
+        0.5,
+        w = 'w',
+    );
+    debug_writeln!(&mut msg, fmt = "plain");
+    assert_eq!(
+        "debug: processed 3 of 10 items\ndebug:    3|ff|0.50|w|10\ndebug: plain\n",
+        msg,
+    );
+}
+
+#[cfg(all(feature = "debug_static", not(feature = "debug_emit")))]
+#[test]
+fn test_static_disabled() {
+    let _lock = test_lock();
+    set_debug(true);
+    assert!(!is_debug());
+    let cap = capture();
+    let mut evaluated = false;
+    debug!("unseen", {
+        evaluated = true;
+    });
+    assert_eq!(debug_val!(5), 5);
+    assert!(!evaluated);
+    assert!(cap.lines().is_empty());
+}
+
+#[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
+fn test_levels() {
+    use std::fmt::Write;
+    let _lock = test_lock();
+    set_level(Some(Level::Info));
+    assert_eq!(level(), Some(Level::Info));
+    assert!(!is_debug());
+    let mut msg = String::new();
+    level_writeln!(&mut msg, Level::Error, "failed", 1);
+    level_writeln!(&mut msg, Level::Info, "status");
+    level_writeln!(&mut msg, Level::Debug, "hidden");
+    level_writeln!(&mut msg, Level::Trace);
+    assert_eq!("error: failed: 1\ninfo: status\n", msg);
+    set_debug(true);
+    assert_eq!(level(), Some(Level::Debug));
+    set_debug(false);
+    assert_eq!(level(), None);
+}
+
+#[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
 fn test_debug() {
+    let _lock = test_lock();
+    set_debug(true);
+    let cap = capture();
     debug!("debugging", "example value");
+    info!("informing");
+    trace!("hidden");
+    assert_eq!(
+        cap.lines(),
+        vec!["debug: debugging: \"example value\"", "info: informing"],
+    );
 }
@@ -0,0 +1,138 @@
+//! Bridge to the `log` crate, with the `log` feature.
+
+use crate::format::write_body;
+use crate::{is_module_enabled, report, Level, Record};
+
+use log::{LevelFilter, Log, Metadata};
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+
+/// True once [init_log] has installed [Logger], so that the per-level macros need not forward
+/// to it.
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+impl From<log::Level> for Level {
+    fn from(level: log::Level) -> Self {
+        match level {
+            log::Level::Error => Level::Error,
+            log::Level::Warn => Level::Warn,
+            log::Level::Info => Level::Info,
+            log::Level::Debug => Level::Debug,
+            log::Level::Trace => Level::Trace,
+        }
+    }
+}
+
+impl From<Level> for log::Level {
+    fn from(level: Level) -> Self {
+        match level {
+            Level::Error => log::Level::Error,
+            Level::Warn => log::Level::Warn,
+            Level::Info => log::Level::Info,
+            Level::Debug => log::Level::Debug,
+            Level::Trace => log::Level::Trace,
+        }
+    }
+}
+
+/// A [log::Log] reporting records from the `log` crate, such as those of dependencies, as if
+/// they came from the per-level macros: subject to this crate's level and module filters, then
+/// formatted and written to the installed [Sink](crate::Sink). The target of each record takes
+/// the place of the module path. Install it with [init_log].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Logger;
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        is_module_enabled(metadata.level().into(), metadata.target())
+    }
+
+    fn log(&self, record: &log::Record) {
+        let location = record.file().zip(record.line());
+        report(
+            record.level().into(),
+            record.target(),
+            location,
+            *record.args(),
+        );
+    }
+
+    fn flush(&self) {
+        let _ = crate::flush_dedup();
+    }
+}
+
+/// Install [Logger] as the logger of the `log` crate and pass it records at every level, so
+/// that `log` output goes through this crate. The per-level macros then write to the installed
+/// [Sink](crate::Sink) directly rather than forwarding to `log`.
+///
+/// Fails if a logger is already installed.
+///
+/// # Examples
+///
+/// ```
+/// # if !debug_macros::__STATIC_ENABLED { return; }
+/// debug_macros::init_log().unwrap();
+/// debug_macros::set_debug(true);
+/// let cap = debug_macros::capture();
+/// log::warn!(target: "hyper::client", "retrying {}", 2);
+/// log::trace!("not shown");
+/// debug_macros::debug!("sent", 3);
+/// assert_eq!(cap.lines(), vec!["warn: retrying 2", "debug: sent: 3"]);
+/// ```
+pub fn init_log() -> Result<(), log::SetLoggerError> {
+    let installed = INSTALLED.swap(true, Relaxed);
+    if let Err(e) = log::set_logger(&Logger) {
+        INSTALLED.store(installed, Relaxed);
+        return Err(e);
+    }
+    log::set_max_level(LevelFilter::Trace);
+    Ok(())
+}
+
+/// Forward `record` to `log` as `log::log!(target: module_path, ...)` would, with its message
+/// and values formatted as in text output. Returns `false`, forwarding nothing, if the logger
+/// is [Logger] or if no logger is installed, as is assumed while `log`'s maximum level is
+/// `Off`.
+pub(crate) fn forward(record: &Record) -> bool {
+    if INSTALLED.load(Relaxed) || log::max_level() == LevelFilter::Off {
+        return false;
+    }
+    let level = log::Level::from(record.level);
+    if level <= log::max_level() {
+        let mut body = String::new();
+        write_body(&mut body, record);
+        let file = Some(record.file).filter(|file| !file.is_empty());
+        log::logger().log(
+            &log::Record::builder()
+                .args(format_args!("{}", body))
+                .level(level)
+                .target(record.module_path)
+                .module_path(Some(record.module_path))
+                .file(file)
+                .line(file.map(|_| record.line))
+                .build(),
+        );
+    }
+    true
+}
+
+#[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
+fn test_logger() {
+    use crate::{capture, debug, set_debug, set_filter};
+    let _lock = crate::test_lock();
+    set_debug(true);
+    init_log().unwrap();
+    assert!(init_log().is_err());
+    set_filter("debug,dep::quiet=warn").unwrap();
+    let cap = capture();
+    log::info!(target: "dep::net", "connected to {}", "db");
+    log::info!(target: "dep::quiet", "hidden");
+    log::error!(target: "dep::quiet", "failed");
+    debug!("direct", 1);
+    crate::clear_filter();
+    assert_eq!(
+        cap.lines(),
+        vec!["info: connected to db", "error: failed", "debug: direct: 1"],
+    );
+}
@@ -0,0 +1,119 @@
+//! logfmt output.
+
+use crate::format::{is_thread, write_thread, Record};
+use crate::time::{elapsed, timestamps, write_rfc3339, Timestamps};
+
+use std::fmt::Write;
+use std::time::SystemTime;
+
+/// Write `key` to `out`, replacing characters not allowed in a logfmt key with `_`.
+fn write_key(out: &mut String, key: &str) {
+    out.push(' ');
+    out.extend(key.chars().map(|c| match c {
+        ' ' | '=' | '"' => '_',
+        c if c.is_control() => '_',
+        c => c,
+    }));
+    out.push('=');
+}
+
+/// Write `value` to `out`, quoted and escaped if necessary.
+fn write_value(out: &mut String, value: &str) {
+    let plain = !value.is_empty()
+        && !value
+            .chars()
+            .any(|c| c == ' ' || c == '=' || c == '"' || c == '\\' || c.is_control());
+    if plain {
+        out.push_str(value);
+        return;
+    }
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Format `record` as [OutputFormat::Logfmt](crate::OutputFormat::Logfmt).
+pub(crate) fn format_logfmt(record: &Record) -> String {
+    let mut out = String::new();
+    match timestamps() {
+        Timestamps::None => (),
+        Timestamps::Rfc3339 => {
+            out.push_str("ts=");
+            write_rfc3339(&mut out, SystemTime::now());
+            out.push(' ');
+        }
+        Timestamps::Elapsed => {
+            let elapsed = elapsed();
+            let (secs, micros) = (elapsed.as_secs(), elapsed.subsec_micros());
+            write!(out, "elapsed={}.{:06} ", secs, micros).unwrap();
+        }
+    }
+    write!(out, "level={}", record.level).unwrap();
+    if let Some(msg) = record.msg {
+        write_key(&mut out, "msg");
+        write_value(&mut out, &msg.to_string());
+    }
+    let mut arg = 0;
+    for (name, value) in record.fields {
+        match name {
+            Some(name) => write_key(&mut out, name),
+            None => {
+                write_key(&mut out, &format!("arg{}", arg));
+                arg += 1;
+            }
+        }
+        write_value(&mut out, &format!("{:?}", value));
+    }
+    if record.suppressed > 0 {
+        write!(out, " suppressed={}", record.suppressed).unwrap();
+    }
+    if is_thread() {
+        let mut thread = String::new();
+        write_thread(&mut thread);
+        write_key(&mut out, "thread");
+        write_value(&mut out, &thread);
+    }
+    if !record.file.is_empty() {
+        write_key(&mut out, "file");
+        write_value(&mut out, record.file);
+        write!(out, " line={}", record.line).unwrap();
+    }
+    out
+}
+
+#[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
+fn test_logfmt() {
+    use crate::{debug_val, debug_writeln, set_debug, set_output_format, OutputFormat};
+    let _lock = crate::test_lock();
+    set_debug(true);
+    set_output_format(OutputFormat::Logfmt);
+    let (i, s) = (3, "a \"b\"=\n");
+    let mut msg = String::new();
+    let line = line!() + 1;
+    debug_writeln!(&mut msg, "round", i, s, 2, "");
+    let cap = crate::capture();
+    debug_val!(i + 1);
+    set_output_format(OutputFormat::Text);
+    let expected = format!(
+        concat!(
+            r#"level=debug msg=round i=3 s="\"a \\\"b\\\"=\\n\"" arg0=2 arg1="\"\"""#,
+            " file={} line={}\n",
+        ),
+        file!(),
+        line,
+    );
+    assert_eq!(expected, msg);
+    let expected = format!("level=debug i_+_1=4 file={} line={}", file!(), line + 2);
+    assert_eq!(cap.lines(), vec![expected]);
+}
@@ -0,0 +1,228 @@
+//! Scoped and per-thread overrides of the debug state.
+
+use crate::{level, set_debug, set_level, Level};
+
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+use std::thread::JoinHandle;
+
+/// Number of threads with an override set, so that the thread-local need not be consulted
+/// when there are none.
+static OVERRIDES: AtomicUsize = AtomicUsize::new(0);
+
+/// This thread's override, counted in [OVERRIDES] while set.
+struct ThreadDebug(Cell<Option<bool>>);
+
+impl Drop for ThreadDebug {
+    fn drop(&mut self) {
+        if self.0.get().is_some() {
+            OVERRIDES.fetch_sub(1, Relaxed);
+        }
+    }
+}
+
+thread_local! {
+    static THREAD_DEBUG: ThreadDebug = const { ThreadDebug(Cell::new(None)) };
+}
+
+/// Set this thread's override, returning the previous one.
+fn replace_thread_debug(debug: Option<bool>) -> Option<bool> {
+    THREAD_DEBUG.with(|t| {
+        let previous = t.0.replace(debug);
+        match (previous, debug) {
+            (None, Some(_)) => {
+                OVERRIDES.fetch_add(1, Relaxed);
+            }
+            (Some(_), None) => {
+                OVERRIDES.fetch_sub(1, Relaxed);
+            }
+            _ => (),
+        }
+        previous
+    })
+}
+
+/// The most verbose level reported on this thread if it has an override, ignoring the global
+/// level and module filters.
+#[inline]
+pub(crate) fn thread_level() -> Option<Option<Level>> {
+    if OVERRIDES.load(Relaxed) == 0 {
+        return None;
+    }
+    thread_level_slow()
+}
+
+#[cold]
+fn thread_level_slow() -> Option<Option<Level>> {
+    THREAD_DEBUG
+        .try_with(|t| t.0.get())
+        .ok()
+        .flatten()
+        .map(|debug| if debug { Some(Level::Debug) } else { None })
+}
+
+/// Override debugging for the current thread: `Some(true)` to report messages at [Level::Debug]
+/// and above, `Some(false)` to report nothing, regardless of the global level and module
+/// filters, or `None` to follow them again. The override lasts until changed or until the
+/// thread exits.
+///
+/// Threads do not inherit the override; use [spawn] or [inherit] to pass it on.
+///
+/// # Examples
+///
+/// ```
+/// # if !debug_macros::__STATIC_ENABLED { return; }
+/// use debug_macros::{is_debug, set_thread_debug};
+/// debug_macros::set_debug(false);
+/// set_thread_debug(Some(true));
+/// assert!(is_debug());
+/// debug_macros::spawn(|| assert!(is_debug())).join().unwrap();
+/// std::thread::spawn(|| assert!(!is_debug())).join().unwrap();
+/// ```
+pub fn set_thread_debug(debug: Option<bool>) {
+    replace_thread_debug(debug);
+}
+
+/// Report the current thread's override, as set by [set_thread_debug] or [scoped_thread].
+pub fn thread_debug() -> Option<bool> {
+    THREAD_DEBUG.try_with(|t| t.0.get()).ok().flatten()
+}
+
+/// Wrap `f` so that it runs with the current thread's override, on whatever thread it is
+/// eventually called, restoring that thread's own override afterward. This passes the override
+/// on to work sent to a thread pool or spawned with a [std::thread::Builder].
+pub fn inherit<F, T>(f: F) -> impl FnOnce() -> T
+where
+    F: FnOnce() -> T,
+{
+    let debug = thread_debug();
+    move || {
+        let _restore = ScopedThreadDebug {
+            previous: replace_thread_debug(debug),
+            _not_send: PhantomData,
+        };
+        f()
+    }
+}
+
+/// As [std::thread::spawn], but the new thread [inherit]s the current thread's override.
+pub fn spawn<F, T>(f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    std::thread::spawn(inherit(f))
+}
+
+/// Guard returned by [scoped]. Restores the previous global level when dropped.
+#[must_use = "debugging is restored when the guard is dropped"]
+#[derive(Debug)]
+pub struct ScopedDebug {
+    previous: Option<Level>,
+}
+
+/// Force debugging on or off as with [set_debug] until the returned guard is dropped, at which
+/// point the previous level is restored.
+///
+/// # Examples
+///
+/// ```
+/// # if !debug_macros::__STATIC_ENABLED { return; }
+/// debug_macros::set_debug(false);
+/// {
+///     let _debug = debug_macros::scoped(true);
+///     assert!(debug_macros::is_debug());
+/// }
+/// assert!(!debug_macros::is_debug());
+/// ```
+pub fn scoped(debug: bool) -> ScopedDebug {
+    let previous = level();
+    set_debug(debug);
+    ScopedDebug { previous }
+}
+
+impl Drop for ScopedDebug {
+    fn drop(&mut self) {
+        set_level(self.previous);
+    }
+}
+
+/// Guard returned by [scoped_thread]. Restores the current thread's previous state when
+/// dropped.
+#[must_use = "debugging is restored when the guard is dropped"]
+#[derive(Debug)]
+pub struct ScopedThreadDebug {
+    previous: Option<bool>,
+    // The guard restores a thread-local, so must stay on its thread.
+    _not_send: PhantomData<*const ()>,
+}
+
+/// Force debugging on or off for the current thread only until the returned guard is dropped.
+/// While it is alive, this thread reports messages at [Level::Debug] and above if `debug` is
+/// true and nothing otherwise, regardless of the global level and module filters.
+///
+/// This lets tests, which run in parallel threads, enable debugging without interfering with
+/// each other.
+pub fn scoped_thread(debug: bool) -> ScopedThreadDebug {
+    ScopedThreadDebug {
+        previous: replace_thread_debug(Some(debug)),
+        _not_send: PhantomData,
+    }
+}
+
+impl Drop for ScopedThreadDebug {
+    fn drop(&mut self) {
+        replace_thread_debug(self.previous);
+    }
+}
+
+#[test]
+fn test_scoped() {
+    let _lock = crate::test_lock();
+    set_level(Some(Level::Trace));
+    {
+        let _debug = scoped(false);
+        assert_eq!(level(), None);
+    }
+    assert_eq!(level(), Some(Level::Trace));
+}
+
+#[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
+fn test_scoped_thread() {
+    use crate::{capture, debug, is_debug, is_module_enabled};
+    let _lock = crate::test_lock();
+    let cap = capture();
+    {
+        let _debug = scoped_thread(true);
+        assert!(is_debug());
+        assert!(!is_module_enabled(Level::Trace, "any::module"));
+        {
+            let _off = scoped_thread(false);
+            debug!("hidden");
+        }
+        debug!("shown");
+    }
+    std::thread::spawn(|| assert_eq!(thread_level(), None))
+        .join()
+        .unwrap();
+    assert_eq!(cap.lines(), vec!["debug: shown"]);
+}
+
+#[test]
+fn test_thread_debug() {
+    use crate::is_debug;
+    std::thread::spawn(|| {
+        set_thread_debug(Some(false));
+        assert!(!is_debug());
+        let child = spawn(|| (thread_debug(), is_debug())).join().unwrap();
+        assert_eq!(child, (Some(false), false));
+        let wrapped = inherit(thread_debug);
+        set_thread_debug(None);
+        assert_eq!(wrapped(), Some(false));
+        assert_eq!(thread_debug(), None);
+    })
+    .join()
+    .unwrap();
+}
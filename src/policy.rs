@@ -0,0 +1,99 @@
+//! Handling of failed writes.
+
+use crate::SeqCst;
+
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::atomic::AtomicUsize;
+
+/// What to do when writing debug output fails, for example because of a closed pipe or a full
+/// disk. Set with [set_write_error_policy].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteErrorPolicy {
+    /// Panic. This is the default.
+    Panic,
+    /// Silently drop the output.
+    Ignore,
+    /// Drop the output, counting the failure in [write_errors].
+    Count,
+    /// Count the failure as for [WriteErrorPolicy::Count], and write the output to
+    /// [std::io::stderr] instead.
+    Stderr,
+}
+
+/// Current policy, as `policy as usize`.
+static POLICY: AtomicUsize = AtomicUsize::new(WriteErrorPolicy::Panic as usize);
+
+/// Number of failed writes counted.
+static FAILURES: AtomicUsize = AtomicUsize::new(0);
+
+/// Set the policy for failed writes by the writing and per-level macros.
+pub fn set_write_error_policy(policy: WriteErrorPolicy) {
+    POLICY.store(policy as usize, SeqCst);
+}
+
+/// Report the current policy for failed writes.
+pub fn write_error_policy() -> WriteErrorPolicy {
+    match POLICY.load(SeqCst) {
+        1 => WriteErrorPolicy::Ignore,
+        2 => WriteErrorPolicy::Count,
+        3 => WriteErrorPolicy::Stderr,
+        _ => WriteErrorPolicy::Panic,
+    }
+}
+
+/// Report the number of failed writes so far under [WriteErrorPolicy::Count] or
+/// [WriteErrorPolicy::Stderr].
+pub fn write_errors() -> usize {
+    FAILURES.load(SeqCst)
+}
+
+/// Handle a failure to write `line` according to the current [WriteErrorPolicy]. Called by the
+/// writing macros.
+#[doc(hidden)]
+pub fn __write_failed<E: fmt::Debug>(error: E, line: &str) {
+    match write_error_policy() {
+        WriteErrorPolicy::Panic => panic!("debug output failed: {:?}", error),
+        WriteErrorPolicy::Ignore => (),
+        WriteErrorPolicy::Count => {
+            FAILURES.fetch_add(1, SeqCst);
+        }
+        WriteErrorPolicy::Stderr => {
+            FAILURES.fetch_add(1, SeqCst);
+            let stderr = io::stderr();
+            let _ = writeln!(stderr.lock(), "{}", line);
+        }
+    }
+}
+
+#[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
+fn test_write_error_policy() {
+    use crate::{debug_writeln, set_debug, try_debug_writeln};
+
+    struct Closed;
+
+    impl Write for Closed {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let _lock = crate::test_lock();
+    set_debug(true);
+    assert!(try_debug_writeln!(Closed, "lost").is_err());
+    set_write_error_policy(WriteErrorPolicy::Count);
+    let before = write_errors();
+    debug_writeln!(Closed, "lost", 1);
+    debug_writeln!(Closed);
+    assert_eq!(write_errors(), before + 2);
+    set_write_error_policy(WriteErrorPolicy::Ignore);
+    debug_writeln!(Closed, "lost");
+    assert_eq!(write_errors(), before + 2);
+    set_write_error_policy(WriteErrorPolicy::Panic);
+    assert!(std::panic::catch_unwind(|| debug_writeln!(Closed, "lost")).is_err());
+}
@@ -0,0 +1,92 @@
+//! Per-callsite state for the rate-limited macros.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering::Relaxed};
+
+/// Rate-limiting state of one invocation of [debug_every](crate::debug_every),
+/// [debug_once](crate::debug_once) or [debug_rate](crate::debug_rate), kept in a `static`.
+///
+/// Each check returns `Some` of the number of calls suppressed since the last emission if the
+/// call should be emitted, and otherwise counts it as suppressed.
+#[doc(hidden)]
+pub struct RateLimit {
+    calls: AtomicU64,
+    suppressed: AtomicUsize,
+    fired: AtomicBool,
+    /// Time of the last emission in nanoseconds since process start, plus one; `0` is never.
+    last: AtomicU64,
+}
+
+impl RateLimit {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        RateLimit {
+            calls: AtomicU64::new(0),
+            suppressed: AtomicUsize::new(0),
+            fired: AtomicBool::new(false),
+            last: AtomicU64::new(0),
+        }
+    }
+
+    fn emit(&self) -> Option<usize> {
+        Some(self.suppressed.swap(0, Relaxed))
+    }
+
+    fn suppress(&self) -> Option<usize> {
+        self.suppressed.fetch_add(1, Relaxed);
+        None
+    }
+
+    /// Emit the first of every `n` calls.
+    pub fn every(&self, n: u64) -> Option<usize> {
+        if self.calls.fetch_add(1, Relaxed).is_multiple_of(n.max(1)) {
+            self.emit()
+        } else {
+            self.suppress()
+        }
+    }
+
+    /// Emit only the first call.
+    pub fn once(&self) -> Option<usize> {
+        if self.fired.swap(true, Relaxed) {
+            self.suppress()
+        } else {
+            self.emit()
+        }
+    }
+
+    /// Emit calls at least `1 / per_sec` seconds apart.
+    pub fn rate(&self, per_sec: f64) -> Option<usize> {
+        let interval = (1e9 / per_sec) as u64;
+        let now = crate::time::start().elapsed().as_nanos() as u64 + 1;
+        let last = self.last.load(Relaxed);
+        // Another thread may have stored a later time since `now` was read.
+        if (last == 0 || now.saturating_sub(last) >= interval)
+            && self
+                .last
+                .compare_exchange(last, now, Relaxed, Relaxed)
+                .is_ok()
+        {
+            self.emit()
+        } else {
+            self.suppress()
+        }
+    }
+}
+
+#[test]
+fn test_rate_limit() {
+    let limit = RateLimit::new();
+    assert_eq!(limit.once(), Some(0));
+    assert_eq!(limit.once(), None);
+    let limit = RateLimit::new();
+    let emitted: Vec<_> = (0..5).map(|_| limit.every(2)).collect();
+    assert_eq!(emitted, vec![Some(0), None, Some(1), None, Some(1)]);
+    let limit = RateLimit::new();
+    assert_eq!(limit.rate(1e-3), Some(0));
+    assert_eq!(limit.rate(1e-3), None);
+    assert_eq!(limit.rate(1e-3), None);
+    let limit = RateLimit::new();
+    assert_eq!(limit.rate(1e12), Some(0));
+    std::thread::sleep(std::time::Duration::from_millis(1));
+    assert_eq!(limit.rate(1e12), Some(0));
+}
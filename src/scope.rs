@@ -0,0 +1,188 @@
+//! Nested scopes indenting debug output.
+
+use crate::{is_module_enabled, EventSite, Level, Record, SeqCst};
+
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::sync::atomic::AtomicBool;
+
+thread_local! {
+    /// Number of [Scope]s active on this thread.
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Indentation of text lines per active [Scope].
+const SCOPE_INDENT: &str = "  ";
+
+/// The indentation of text lines for the [Scope]s active on this thread.
+pub(crate) fn indent() -> String {
+    SCOPE_INDENT.repeat(DEPTH.try_with(Cell::get).unwrap_or(0))
+}
+
+/// Whether to report entry to and exit from each [Scope].
+static SCOPE_LINES: AtomicBool = AtomicBool::new(false);
+
+/// Report or omit lines marking where each [debug_scope](crate::debug_scope) is entered and
+/// exited, as `debug: enter name` and `debug: exit name` at the depth of the enclosing scope.
+/// They are omitted by default.
+pub fn set_scope_lines(lines: bool) {
+    SCOPE_LINES.store(lines, SeqCst);
+}
+
+/// Report whether scope entry and exit lines are reported.
+pub fn is_scope_lines() -> bool {
+    SCOPE_LINES.load(SeqCst)
+}
+
+/// Guard returned by [debug_scope](crate::debug_scope). Ends the scope, unindenting later
+/// output on its thread, when dropped.
+#[must_use = "the scope ends when the guard is dropped"]
+#[derive(Debug)]
+pub struct Scope {
+    name: &'static str,
+    module_path: &'static str,
+    /// Whether the scope was entered, which it is only if debugging was enabled.
+    entered: bool,
+    /// Whether the scope's entry was reported, so its exit should be.
+    lines: bool,
+    /// Sites of the entry and exit reports of the [debug_scope](crate::debug_scope) invocation.
+    sites: &'static [EventSite; 2],
+    // The guard restores a thread-local, so must stay on its thread.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Scope {
+    /// Enter the scope `name` at `location` in `module_path` if `enabled`. Called by
+    /// [debug_scope](crate::debug_scope).
+    #[doc(hidden)]
+    pub fn __new(
+        enabled: bool,
+        name: &'static str,
+        module_path: &'static str,
+        location: (&'static str, u32, u32),
+        sites: &'static [EventSite; 2],
+    ) -> Self {
+        let lines = enabled && is_scope_lines();
+        if lines {
+            report("enter", name, module_path, location, &sites[0]);
+        }
+        if enabled {
+            DEPTH.with(|d| d.set(d.get() + 1));
+        }
+        Scope {
+            name,
+            module_path,
+            entered: enabled,
+            lines,
+            sites,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// A scope that is never entered, for [debug_scope](crate::debug_scope) when debugging is
+    /// disabled at compile time.
+    #[doc(hidden)]
+    pub fn __disabled() -> Self {
+        static SITES: [EventSite; 2] = [EventSite::new(), EventSite::new()];
+        Scope {
+            name: "",
+            module_path: "",
+            entered: false,
+            lines: false,
+            sites: &SITES,
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        if self.entered {
+            let _ = DEPTH.try_with(|d| d.set(d.get() - 1));
+        }
+        if self.lines && is_module_enabled(Level::Debug, self.module_path) {
+            let site = &self.sites[1];
+            report("exit", self.name, self.module_path, ("", 0, 0), site);
+        }
+    }
+}
+
+/// Report `event`, entry or exit, for the scope `name` from `site`.
+fn report(
+    event: &str,
+    name: &str,
+    module_path: &str,
+    (file, line, column): (&str, u32, u32),
+    site: &'static EventSite,
+) {
+    let record = Record {
+        level: Level::Debug,
+        module_path,
+        file,
+        line,
+        column,
+        msg: Some(format_args!("{} {}", event, name)),
+        fields: &[],
+        pretty: false,
+        suppressed: 0,
+    };
+    crate::__emit_record(&record, site);
+}
+
+#[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
+fn test_scope() {
+    use crate::{debug_scope, debug_writeln, set_debug, set_pretty};
+    use std::fmt::Write;
+    let _lock = crate::test_lock();
+    set_debug(true);
+    let mut msg = String::new();
+    {
+        let _outer = debug_scope!("outer");
+        debug_writeln!(&mut msg, "a", 1);
+        {
+            let _inner = debug_scope!("inner");
+            set_pretty(true);
+            debug_writeln!(&mut msg, "b", (2,));
+            set_pretty(false);
+        }
+        debug_writeln!(&mut msg, "c");
+    }
+    set_debug(false);
+    {
+        let _hidden = debug_scope!("hidden");
+        set_debug(true);
+        debug_writeln!(&mut msg, "d");
+    }
+    let expected =
+        "  debug: a: 1\n    debug: b: (\n            2,\n        )\n  debug: c\ndebug: d\n";
+    assert_eq!(expected, msg);
+
+    set_scope_lines(true);
+    let cap = crate::capture();
+    {
+        let _outer = debug_scope!("outer");
+        let _inner = debug_scope!("inner");
+        crate::debug!("e");
+    }
+    set_scope_lines(false);
+    assert_eq!(
+        cap.lines(),
+        vec![
+            "debug: enter outer",
+            "  debug: enter inner",
+            "    debug: e",
+            "  debug: exit inner",
+            "debug: exit outer",
+        ],
+    );
+
+    set_scope_lines(true);
+    let cap = crate::capture();
+    {
+        let _scope = debug_scope!("off");
+        set_debug(false);
+    }
+    set_scope_lines(false);
+    assert_eq!(cap.lines(), vec!["debug: enter off"]);
+}
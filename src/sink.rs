@@ -0,0 +1,172 @@
+//! Output destinations for the per-level macros.
+
+use crate::Level;
+
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex, RwLock};
+
+/// A destination for lines written by [debug](crate::debug) and the other per-level macros.
+/// Install one with [set_sink].
+///
+/// Closures `Fn(&str) -> io::Result<()>` are sinks, which makes it easy to send output to a
+/// channel or other user-defined destination.
+pub trait Sink: Send + Sync {
+    /// Write one line of output. `line` does not include a trailing newline.
+    fn write_line(&self, line: &str) -> io::Result<()>;
+
+    /// Write one line of output for a message at `level` from the module (or other
+    /// [report](crate::report) target) `target`. The default ignores these and calls
+    /// [Sink::write_line].
+    ///
+    /// Override this to forward output to a system with its own levels and targets. Such a sink
+    /// must not be combined with an adapter that sends that system's messages back through
+    /// [report](crate::report), such as the `Logger` of the `log` feature, or messages will
+    /// loop.
+    fn write_record(&self, _level: Level, _target: &str, line: &str) -> io::Result<()> {
+        self.write_line(line)
+    }
+}
+
+impl<F> Sink for F
+where
+    F: Fn(&str) -> io::Result<()> + Send + Sync,
+{
+    fn write_line(&self, line: &str) -> io::Result<()> {
+        self(line)
+    }
+}
+
+/// The default [Sink]: writes each line to a locked [std::io::stderr].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StderrSink;
+
+impl Sink for StderrSink {
+    fn write_line(&self, line: &str) -> io::Result<()> {
+        let stderr = io::stderr();
+        let mut stderr = stderr.lock();
+        writeln!(stderr, "{}", line)
+    }
+}
+
+/// A [Sink] writing newline-terminated lines to any [std::io::Write], such as a
+/// [std::fs::File].
+#[derive(Debug)]
+pub struct WriterSink<W>(Mutex<W>);
+
+impl<W: Write + Send> WriterSink<W> {
+    /// Make a sink writing to `writer`.
+    pub fn new(writer: W) -> Self {
+        WriterSink(Mutex::new(writer))
+    }
+}
+
+impl<W: Write + Send> Sink for WriterSink<W> {
+    fn write_line(&self, line: &str) -> io::Result<()> {
+        let mut writer = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        writeln!(writer, "{}", line)?;
+        writer.flush()
+    }
+}
+
+/// A [Sink] collecting lines in memory. Clones share the same buffer, so one clone can be
+/// installed with [set_sink] while another is used to read back the output.
+///
+/// # Examples
+///
+/// ```
+/// # if !debug_macros::__STATIC_ENABLED { return; }
+/// use debug_macros::{debug, MemorySink};
+/// let buffer = MemorySink::new();
+/// debug_macros::set_sink(Box::new(buffer.clone()));
+/// debug_macros::set_debug(true);
+/// debug!("saved", 7);
+/// assert_eq!(buffer.lines(), vec!["debug: saved: 7"]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemorySink(Arc<Mutex<Vec<String>>>);
+
+impl MemorySink {
+    /// Make an empty sink.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The lines written so far.
+    pub fn lines(&self) -> Vec<String> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+impl Sink for MemorySink {
+    fn write_line(&self, line: &str) -> io::Result<()> {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(line.to_string());
+        Ok(())
+    }
+}
+
+/// The installed sink, or `None` for [StderrSink].
+static SINK: RwLock<Option<Box<dyn Sink>>> = RwLock::new(None);
+
+/// Send the output of the per-level macros to `sink` in place of [std::io::stderr].
+pub fn set_sink(sink: Box<dyn Sink>) {
+    *SINK.write().unwrap_or_else(|e| e.into_inner()) = Some(sink);
+}
+
+/// Restore the default [StderrSink].
+pub fn reset_sink() {
+    *SINK.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Write `line`, a message at `level` from `target`, to the active [capture](crate::capture)
+/// on this thread, or else to the installed sink by way of [set_dedup](crate::set_dedup).
+pub(crate) fn emit(level: Level, target: &str, line: &str) -> io::Result<()> {
+    if crate::capture::capture_line(line) {
+        return Ok(());
+    }
+    crate::dedup::emit(level, target, line)
+}
+
+/// Write `line`, a message at `level` from `target`, to the installed sink.
+pub(crate) fn write_sink(level: Level, target: &str, line: &str) -> io::Result<()> {
+    match &*SINK.read().unwrap_or_else(|e| e.into_inner()) {
+        Some(sink) => sink.write_record(level, target, line),
+        None => StderrSink.write_record(level, target, line),
+    }
+}
+
+#[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
+fn test_write_record() {
+    use crate::{info, report, reset_sink, set_debug, set_sink};
+
+    struct Records(Mutex<Vec<(Level, String)>>);
+
+    impl Sink for Arc<Records> {
+        fn write_line(&self, _: &str) -> io::Result<()> {
+            unreachable!()
+        }
+
+        fn write_record(&self, level: Level, target: &str, _: &str) -> io::Result<()> {
+            self.0.lock().unwrap().push((level, target.to_string()));
+            Ok(())
+        }
+    }
+
+    let _lock = crate::test_lock();
+    set_debug(true);
+    let records = Arc::new(Records(Mutex::new(Vec::new())));
+    set_sink(Box::new(Arc::clone(&records)));
+    info!("here");
+    report(Level::Warn, "elsewhere", None, format_args!("there"));
+    reset_sink();
+    assert_eq!(
+        *records.0.lock().unwrap(),
+        vec![
+            (Level::Info, "debug_macros::sink".to_string()),
+            (Level::Warn, "elsewhere".to_string()),
+        ],
+    );
+}
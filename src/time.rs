@@ -0,0 +1,176 @@
+//! Timestamps for output lines.
+
+use crate::SeqCst;
+
+use std::fmt::Write;
+use std::sync::atomic::AtomicUsize;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Kind of timestamp to prefix to each line. Set with [set_timestamps].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timestamps {
+    /// No timestamp. This is the default.
+    None,
+    /// Wall-clock UTC time in RFC 3339 format, as in `2026-10-15T17:03:12.345678Z`.
+    Rfc3339,
+    /// Monotonic time elapsed since the process started, in seconds, as in `12.345678s`.
+    /// The start is taken to be the first use of this crate's debug state.
+    Elapsed,
+}
+
+/// Current timestamp kind, as `timestamps as usize`.
+static TIMESTAMPS: AtomicUsize = AtomicUsize::new(Timestamps::None as usize);
+
+/// Instant taken as the process start for [Timestamps::Elapsed].
+static START: OnceLock<Instant> = OnceLock::new();
+
+/// Choose the timestamp prefixed to each line by the writing and per-level macros.
+pub fn set_timestamps(timestamps: Timestamps) {
+    TIMESTAMPS.store(timestamps as usize, SeqCst);
+}
+
+/// Report the timestamp currently prefixed to each line.
+pub fn timestamps() -> Timestamps {
+    match TIMESTAMPS.load(SeqCst) {
+        1 => Timestamps::Rfc3339,
+        2 => Timestamps::Elapsed,
+        _ => Timestamps::None,
+    }
+}
+
+/// The process start for [Timestamps::Elapsed], taken on first call.
+pub(crate) fn start() -> Instant {
+    *START.get_or_init(Instant::now)
+}
+
+/// Time elapsed since the process start for [Timestamps::Elapsed].
+pub(crate) fn elapsed() -> Duration {
+    start().elapsed()
+}
+
+/// Write the current timestamp, followed by a space, to `out`. Writes nothing for
+/// [Timestamps::None].
+pub(crate) fn write_timestamp(out: &mut String) {
+    match timestamps() {
+        Timestamps::None => (),
+        Timestamps::Rfc3339 => {
+            write_rfc3339(out, SystemTime::now());
+            out.push(' ');
+        }
+        Timestamps::Elapsed => {
+            let elapsed = elapsed();
+            write!(
+                out,
+                "{}.{:06}s ",
+                elapsed.as_secs(),
+                elapsed.subsec_micros()
+            )
+            .unwrap();
+        }
+    }
+}
+
+/// Write `time` to `out` as an RFC 3339 UTC timestamp with microseconds.
+pub(crate) fn write_rfc3339(out: &mut String, time: SystemTime) {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO);
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let secs_of_day = secs % 86_400;
+    write!(
+        out,
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60,
+        since_epoch.subsec_micros(),
+    )
+    .unwrap();
+}
+
+/// Convert days since 1970-01-01 to a proleptic Gregorian (year, month, day). This is Howard
+/// Hinnant's `civil_from_days` algorithm.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[test]
+fn test_rfc3339() {
+    let at = |secs: u64, micros: u32| {
+        let mut out = String::new();
+        write_rfc3339(&mut out, UNIX_EPOCH + Duration::new(secs, micros * 1000));
+        out
+    };
+    assert_eq!(at(0, 0), "1970-01-01T00:00:00.000000Z");
+    assert_eq!(at(951_782_400, 5), "2000-02-29T00:00:00.000005Z");
+    assert_eq!(at(1_700_000_000, 123_456), "2023-11-14T22:13:20.123456Z");
+}
+
+#[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
+fn test_elapsed() {
+    use crate::{debug, reset_sink, set_debug, set_dedup, set_sink, MemorySink};
+    use crate::{set_output_format, OutputFormat};
+    let _lock = crate::test_lock();
+    set_debug(true);
+    let buffer = MemorySink::new();
+    set_sink(Box::new(buffer.clone()));
+    set_timestamps(Timestamps::Elapsed);
+    debug!("text");
+    set_output_format(OutputFormat::Json);
+    debug!("json");
+    set_output_format(OutputFormat::Logfmt);
+    debug!("logfmt");
+    set_output_format(OutputFormat::Text);
+    set_dedup(Some(Duration::from_secs(3600)));
+    for _ in 0..3 {
+        std::thread::sleep(Duration::from_millis(2));
+        debug!("poll");
+    }
+    set_dedup(None);
+    set_timestamps(Timestamps::None);
+    reset_sink();
+
+    // Seconds with six decimal places, as in `12.345678`.
+    let is_secs = |ts: &str| {
+        let digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        ts.split_once('.').is_some_and(|(secs, micros)| {
+            !secs.is_empty() && digits(secs) && micros.len() == 6 && digits(micros)
+        })
+    };
+    let lines = buffer.lines();
+    assert_eq!(lines.len(), 5);
+    let (ts, rest) = lines[0].split_once("s ").unwrap();
+    assert!(is_secs(ts), "{}", lines[0]);
+    assert_eq!(rest, "debug: text");
+    let (ts, rest) = lines[1]
+        .strip_prefix(r#"{"elapsed":"#)
+        .and_then(|line| line.split_once(','))
+        .unwrap();
+    assert!(is_secs(ts), "{}", lines[1]);
+    assert!(rest.starts_with(r#""level":"debug","msg":"json","#));
+    let (ts, rest) = lines[2]
+        .strip_prefix("elapsed=")
+        .and_then(|line| line.split_once(' '))
+        .unwrap();
+    assert!(is_secs(ts), "{}", lines[2]);
+    assert!(rest.starts_with("level=debug msg=logfmt "));
+    let (ts, rest) = lines[3].split_once("s ").unwrap();
+    assert!(is_secs(ts), "{}", lines[3]);
+    assert_eq!(rest, "debug: poll");
+    let (ts, rest) = lines[4].split_once("s ").unwrap();
+    assert!(is_secs(ts), "{}", lines[4]);
+    assert_eq!(rest, "debug: poll (repeated 2 times)");
+}
@@ -0,0 +1,298 @@
+//! Bridge to the `tracing` ecosystem, with the `tracing` feature.
+
+use crate::{EventSite, Level, Record};
+
+use std::fmt;
+use tracing::callsite::{self, Callsite, Identifier};
+use tracing::field::{DebugValue, Field, FieldSet, Value, Visit};
+use tracing::metadata::Kind;
+use tracing::subscriber::{Interest, NoSubscriber};
+use tracing::{dispatcher, Event, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+impl From<tracing::Level> for Level {
+    fn from(level: tracing::Level) -> Self {
+        match level {
+            tracing::Level::ERROR => Level::Error,
+            tracing::Level::WARN => Level::Warn,
+            tracing::Level::INFO => Level::Info,
+            tracing::Level::DEBUG => Level::Debug,
+            tracing::Level::TRACE => Level::Trace,
+        }
+    }
+}
+
+impl From<Level> for tracing::Level {
+    fn from(level: Level) -> Self {
+        match level {
+            Level::Error => tracing::Level::ERROR,
+            Level::Warn => tracing::Level::WARN,
+            Level::Info => tracing::Level::INFO,
+            Level::Debug => tracing::Level::DEBUG,
+            Level::Trace => tracing::Level::TRACE,
+        }
+    }
+}
+
+/// Names of the positional values of events, as their index.
+const POSITIONS: [&str; 16] = [
+    "0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11", "12", "13", "14", "15",
+];
+
+impl Callsite for EventSite {
+    fn set_interest(&self, _: Interest) {}
+
+    fn metadata(&self) -> &Metadata<'_> {
+        self.metadata.get().unwrap()
+    }
+}
+
+fn leak(s: &str) -> &'static str {
+    Box::leak(s.to_string().into_boxed_str())
+}
+
+/// The metadata of `site`, made from `record` and registered with `tracing` on first use.
+fn metadata(site: &'static EventSite, record: &Record) -> &'static Metadata<'static> {
+    let metadata = site.metadata.get_or_init(|| {
+        let mut names = vec!["message", "suppressed"];
+        let mut position = 0;
+        for (name, _) in record.fields {
+            names.push(name.unwrap_or_else(|| {
+                position += 1;
+                POSITIONS.get(position - 1).copied().unwrap_or("_")
+            }));
+        }
+        let file = Some(record.file).filter(|file| !file.is_empty()).map(leak);
+        let target = leak(record.module_path);
+        let name = match file {
+            Some(file) => leak(&format!("event {}:{}", file, record.line)),
+            None => "event",
+        };
+        Metadata::new(
+            name,
+            target,
+            record.level.into(),
+            file,
+            file.map(|_| record.line),
+            Some(target),
+            FieldSet::new(names.leak(), Identifier(site)),
+            Kind::EVENT,
+        )
+    });
+    site.registered.call_once(|| callsite::register(site));
+    metadata
+}
+
+/// Forward `record` from `site` as a `tracing` event whose target is the module path, with
+/// fields `message` for the message, `suppressed` for any count of suppressed messages, and one
+/// for each value: named values by their name and positional values by their index, from `0`.
+/// Returns `false`, forwarding nothing, if no subscriber is installed.
+pub(crate) fn forward(record: &Record, site: &'static EventSite) -> bool {
+    dispatcher::get_default(|dispatch| {
+        if dispatch.is::<NoSubscriber>() {
+            return false;
+        }
+        let metadata = metadata(site, record);
+        if !dispatch.enabled(metadata) {
+            return true;
+        }
+        let suppressed = record.suppressed as u64;
+        let values: Vec<DebugValue<&dyn fmt::Debug>> = record
+            .fields
+            .iter()
+            .map(|(_, value)| tracing::field::debug(*value))
+            .collect();
+        let mut all: Vec<Option<&dyn Value>> = vec![
+            record.msg.as_ref().map(|msg| msg as &dyn Value),
+            Some(&suppressed as &dyn Value).filter(|_| suppressed > 0),
+        ];
+        all.extend(values.iter().map(|value| Some(value as &dyn Value)));
+        dispatch.event(&Event::new(
+            metadata,
+            &metadata.fields().value_set_all(&all),
+        ));
+        true
+    })
+}
+
+/// A [tracing_subscriber::Layer] rendering events in this crate's format, as if they came from
+/// the per-level macros: subject to this crate's level and module filters, then formatted and
+/// written to the installed [Sink](crate::Sink). The target of each event takes the place of
+/// the module path.
+///
+/// The `message` field is the message; the other fields are values, named by their field
+/// names, except that fields named by an index, as in events forwarded from the per-level
+/// macros, are positional.
+///
+/// # Examples
+///
+/// ```
+/// # if !debug_macros::__STATIC_ENABLED { return; }
+/// use debug_macros::{debug, TracingLayer};
+/// use tracing_subscriber::layer::SubscriberExt;
+///
+/// debug_macros::set_debug(true);
+/// let cap = debug_macros::capture();
+/// let subscriber = tracing_subscriber::registry().with(TracingLayer);
+/// tracing::subscriber::with_default(subscriber, || {
+///     tracing::info!(target: "server", id = 7, "accepted {}", "conn");
+///     let i = 3;
+///     debug!("round", i, 4);
+/// });
+/// assert_eq!(
+///     cap.lines(),
+///     vec![r#"info: accepted conn: id = 7"#, "debug: round: i = 3, 4"],
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingLayer;
+
+/// Text of a field value, printed as is.
+struct Text(String);
+
+impl fmt::Debug for Text {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// The message and values of an event.
+#[derive(Default)]
+struct Fields {
+    message: Option<Text>,
+    suppressed: usize,
+    values: Vec<(Option<&'static str>, Text)>,
+}
+
+impl Visit for Fields {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        match field.name() {
+            "suppressed" => self.suppressed = value as usize,
+            _ => self.record_debug(field, &value),
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        let text = Text(format!("{:?}", value));
+        match field.name() {
+            "message" => self.message = Some(text),
+            name if name.parse::<usize>().is_ok() => self.values.push((None, text)),
+            name => self.values.push((Some(name), text)),
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for TracingLayer {
+    fn on_event(&self, event: &Event, _: Context<S>) {
+        let metadata = event.metadata();
+        let level = Level::from(*metadata.level());
+        if !crate::is_module_enabled(level, metadata.target()) {
+            return;
+        }
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let values: Vec<(Option<&'static str>, &dyn fmt::Debug)> = fields
+            .values
+            .iter()
+            .map(|(name, value)| (*name, value as &dyn fmt::Debug))
+            .collect();
+        let emit = |msg| {
+            let record = Record {
+                level,
+                module_path: metadata.target(),
+                file: metadata.file().unwrap_or(""),
+                line: metadata.line().unwrap_or(0),
+                column: 0,
+                msg,
+                fields: &values,
+                pretty: false,
+                suppressed: fields.suppressed,
+            };
+            crate::__emit(level, metadata.target(), &crate::__format(&record));
+        };
+        match &fields.message {
+            Some(message) => emit(Some(format_args!("{}", message.0))),
+            None => emit(None),
+        }
+    }
+}
+
+#[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
+fn test_forward() {
+    use crate::{capture, debug, set_debug};
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    type Events = Arc<Mutex<Vec<(tracing::Level, String, String)>>>;
+
+    struct Collect(Events);
+
+    impl<S: Subscriber> Layer<S> for Collect {
+        fn on_event(&self, event: &Event, _: Context<S>) {
+            let mut fields = Fields::default();
+            event.record(&mut fields);
+            let mut text = format!("{:?}", fields.message);
+            for (name, value) in &fields.values {
+                text.push_str(&format!(" {:?}={:?}", name, value));
+            }
+            let metadata = event.metadata();
+            let target = metadata.target().to_string();
+            self.0
+                .lock()
+                .unwrap()
+                .push((*metadata.level(), target, text));
+        }
+    }
+
+    let _lock = crate::test_lock();
+    set_debug(true);
+    let cap = capture();
+    let i = 3;
+    debug!("before", i);
+    let events = Events::default();
+    let subscriber = tracing_subscriber::registry().with(Collect(events.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        debug!("round", i, 4);
+        debug!("done", n = 1);
+    });
+    assert_eq!(cap.lines(), vec!["debug: before: i = 3"]);
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![
+            (
+                tracing::Level::DEBUG,
+                "debug_macros::tracing_bridge".to_string(),
+                r#"Some(round) Some("i")=3 None=4"#.to_string(),
+            ),
+            (
+                tracing::Level::DEBUG,
+                "debug_macros::tracing_bridge".to_string(),
+                r#"Some(done) Some("n")=1"#.to_string(),
+            ),
+        ],
+    );
+}
+
+#[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
+fn test_layer() {
+    use crate::{capture, set_debug, set_filter};
+    use tracing_subscriber::layer::SubscriberExt;
+    let _lock = crate::test_lock();
+    set_debug(true);
+    set_filter("debug,dep::quiet=warn").unwrap();
+    let cap = capture();
+    let subscriber = tracing_subscriber::registry().with(TracingLayer);
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!(target: "dep::net", peer = "db", "connected");
+        tracing::info!(target: "dep::quiet", "hidden");
+        tracing::error!(target: "dep::quiet", code = 5);
+        tracing::trace!(target: "dep::net", "not shown");
+    });
+    crate::clear_filter();
+    assert_eq!(
+        cap.lines(),
+        vec![r#"info: connected: peer = "db""#, "error: code = 5"],
+    );
+}
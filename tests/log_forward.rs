@@ -0,0 +1,50 @@
+//! Forwarding of the per-level macros to a `log` logger other than `debug_macros::Logger`. This
+//! installs a global logger, so it runs in its own process.
+#![cfg(feature = "log")]
+
+use debug_macros::{capture, debug, info, set_debug};
+use std::sync::Mutex;
+
+static RECORDS: Mutex<Vec<(log::Level, String, String, bool)>> = Mutex::new(Vec::new());
+
+struct Collect;
+
+impl log::Log for Collect {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        RECORDS.lock().unwrap().push((
+            record.level(),
+            record.target().to_string(),
+            record.args().to_string(),
+            record.file() == Some(file!()),
+        ));
+    }
+
+    fn flush(&self) {}
+}
+
+#[test]
+#[cfg_attr(all(feature = "debug_static", not(feature = "debug_emit")), ignore)]
+fn test_forward() {
+    set_debug(true);
+    let cap = capture();
+    let i = 3;
+    debug!("before", i);
+    log::set_logger(&Collect).unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+    debug!("round", i);
+    info!("done", i, total = 6);
+    assert_eq!(cap.lines(), vec!["debug: before: i = 3"]);
+    assert_eq!(
+        *RECORDS.lock().unwrap(),
+        vec![(
+            log::Level::Info,
+            "log_forward".to_string(),
+            "done: i = 3, total = 6".to_string(),
+            true,
+        )],
+    );
+}
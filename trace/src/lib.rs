@@ -0,0 +1,300 @@
+//! The `#[trace_fn]` attribute of `debug_macros`, which re-exports it. Depend on
+//! `debug_macros` rather than on this crate.
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// Report entry to and exit from a function at `Level::Debug`, as
+/// `debug: enter name: a = 1, b = 2` and `debug: exit name: value (elapsed)`.
+///
+/// The arguments named by plain identifiers are reported on entry; `self` and arguments bound by
+/// other patterns are not. The return value is reported on exit, however the function returns,
+/// along with the time taken. Reported arguments and the return value must implement
+/// [std::fmt::Debug]: arguments that do not, or that are too large to be worth reporting, can be
+/// left out with `#[trace_fn(skip(a, b))]`. Nothing is reported, or timed, unless messages at
+/// `Level::Debug` from the function's module are enabled when it is called.
+///
+/// The body of the function is run in a closure, so this does not apply to `async` or `const`
+/// functions. The expansion refers to the crate as `::debug_macros`, so it must not be renamed.
+///
+/// The attribute is named `trace_fn` rather than `trace` because attributes share a namespace
+/// with macros, so `debug_macros::trace` is already the `trace!` macro.
+///
+/// # Examples
+///
+/// ```
+/// # if !debug_macros::__STATIC_ENABLED { return; }
+/// use debug_macros::trace_fn;
+///
+/// #[trace_fn]
+/// fn parse(s: &str, radix: u32) -> Result<u32, std::num::ParseIntError> {
+///     let n = u32::from_str_radix(s, radix)?;
+///     Ok(n + 1)
+/// }
+///
+/// debug_macros::set_debug(true);
+/// let cap = debug_macros::capture();
+/// assert!(parse("x", 10).is_err());
+/// let lines = cap.lines();
+/// assert_eq!(lines[0], "debug: enter parse: s = \"x\", radix = 10");
+/// assert!(lines[1].starts_with("debug: exit parse: Err(ParseIntError { kind: InvalidDigit }) ("));
+/// ```
+///
+/// # Errors
+///
+/// `async` and `const` functions are rejected:
+///
+/// ```compile_fail
+/// #[debug_macros::trace_fn]
+/// async fn fetch(id: u32) -> u32 {
+///     id
+/// }
+/// ```
+///
+/// ```compile_fail
+/// #[debug_macros::trace_fn]
+/// const fn double(n: u32) -> u32 {
+///     n * 2
+/// }
+/// ```
+///
+/// as are arguments to the attribute other than `skip(...)`:
+///
+/// ```compile_fail
+/// #[debug_macros::trace_fn(level = "info")]
+/// fn double(n: u32) -> u32 {
+///     n * 2
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn trace_fn(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let skip = match skipped(attr) {
+        Ok(skip) => skip,
+        Err((span, msg)) => return error(span, msg, item),
+    };
+    match expand(item.clone(), &skip) {
+        Ok(tokens) => tokens,
+        Err((span, msg)) => error(span, msg, item),
+    }
+}
+
+/// The names of the arguments listed in `attr`, which is empty or `skip(a, b, ...)`.
+fn skipped(attr: TokenStream) -> Result<Vec<String>, (Span, &'static str)> {
+    let attr: Vec<TokenTree> = attr.into_iter().collect();
+    match &attr[..] {
+        [] => Ok(Vec::new()),
+        [skip, TokenTree::Group(names)]
+            if is_ident(skip, "skip") && names.delimiter() == Delimiter::Parenthesis =>
+        {
+            names
+                .stream()
+                .into_iter()
+                .filter(|t| !is_punct(t, ','))
+                .map(|t| match t {
+                    TokenTree::Ident(name) => Ok(name.to_string()),
+                    t => Err((t.span(), "expected an argument name")),
+                })
+                .collect()
+        }
+        [t, ..] => Err((t.span(), "expected `skip(...)`")),
+    }
+}
+
+/// Rewrite the function `item` to report its entry and exit, with the arguments not named in
+/// `skip`.
+fn expand(item: TokenStream, skip: &[String]) -> Result<TokenStream, (Span, &'static str)> {
+    let mut head: Vec<TokenTree> = item.into_iter().collect();
+    let body = match head.pop() {
+        Some(TokenTree::Group(body)) if body.delimiter() == Delimiter::Brace => body,
+        other => {
+            let span = other.map_or_else(Span::call_site, |t| t.span());
+            return Err((span, "#[trace_fn] applies only to functions with a body"));
+        }
+    };
+    let not_fn = (Span::call_site(), "#[trace_fn] applies only to functions");
+    let fn_pos = head.iter().position(|t| is_ident(t, "fn")).ok_or(not_fn)?;
+    if let Some(t) = head[..fn_pos]
+        .iter()
+        .find(|t| is_ident(t, "async") || is_ident(t, "const"))
+    {
+        return Err((
+            t.span(),
+            "#[trace_fn] does not support async or const functions",
+        ));
+    }
+    let name = match head.get(fn_pos + 1) {
+        Some(TokenTree::Ident(name)) => name.to_string(),
+        _ => return Err(not_fn),
+    };
+    let mut i = fn_pos + 2;
+    if head.get(i).is_some_and(|t| is_punct(t, '<')) {
+        i = skip_generics(&head, i);
+    }
+    let params = match head.get(i) {
+        Some(TokenTree::Group(params)) if params.delimiter() == Delimiter::Parenthesis => params,
+        _ => return Err(not_fn),
+    };
+    let ret = match &head[i + 1..] {
+        [arrow_1, arrow_2, rest @ ..] if is_punct(arrow_1, '-') && is_punct(arrow_2, '>') => {
+            let end = rest.iter().position(|t| is_ident(t, "where"));
+            Some(&rest[..end.unwrap_or(rest.len())])
+        }
+        _ => None,
+    };
+
+    // let __trace_start = ::debug_macros::__trace_enter!("enter name", a, b);
+    let mut enter = vec![TokenTree::Literal(Literal::string(&format!(
+        "enter {}",
+        name
+    )))];
+    for param in param_names(params) {
+        if skip.contains(&param.to_string()) {
+            continue;
+        }
+        enter.push(Punct::new(',', Spacing::Alone).into());
+        enter.push(param.into());
+    }
+    let mut inner = generated("let __trace_start = ::debug_macros::__trace_enter!");
+    inner.extend(Some(group(
+        Delimiter::Parenthesis,
+        enter.into_iter().collect(),
+    )));
+    inner.extend(generated(";"));
+
+    // let __trace_ret = ::debug_macros::__trace_call(move || -> Ret { body });
+    let mut call = generated("move ||");
+    match ret {
+        // Closures cannot return `impl Trait`, so leave such return types to inference.
+        Some(ret) if ret.iter().any(contains_impl) => {}
+        Some(ret) => {
+            call.extend(generated("->"));
+            call.extend(ret.iter().cloned());
+        }
+        None => call.extend(generated("-> ()")),
+    }
+    call.extend(Some(TokenTree::Group(body)));
+    inner.extend(generated("let __trace_ret = ::debug_macros::__trace_call"));
+    inner.extend(Some(group(Delimiter::Parenthesis, call)));
+    inner.extend(generated(&format!(
+        "; ::debug_macros::__trace_exit!(__trace_start, {:?}, __trace_ret); __trace_ret",
+        format!("exit {}", name),
+    )));
+
+    let mut out: TokenStream = head.into_iter().collect();
+    out.extend(Some(group(Delimiter::Brace, inner)));
+    Ok(out)
+}
+
+/// Index just past the generic parameters of a function, which start with the `<` at `start`.
+fn skip_generics(tokens: &[TokenTree], start: usize) -> usize {
+    let mut depth = 0;
+    for (i, t) in tokens.iter().enumerate().skip(start) {
+        if is_punct(t, '<') {
+            depth += 1;
+        } else if is_punct(t, '>') && !is_punct(&tokens[i - 1], '-') {
+            depth -= 1;
+            if depth == 0 {
+                return i + 1;
+            }
+        }
+    }
+    tokens.len()
+}
+
+/// The names of the parameters in `params` bound by a plain identifier, as in `x: T` or
+/// `mut x: T`.
+fn param_names(params: &Group) -> Vec<Ident> {
+    let mut names = Vec::new();
+    let (mut param, mut depth) = (Vec::new(), 0);
+    let tokens: Vec<TokenTree> = params.stream().into_iter().collect();
+    for (i, t) in tokens.iter().enumerate() {
+        if is_punct(t, '<') {
+            depth += 1;
+        } else if is_punct(t, '>') && !(i > 0 && is_punct(&tokens[i - 1], '-')) {
+            depth -= 1;
+        } else if is_punct(t, ',') && depth == 0 {
+            names.extend(param_name(&param));
+            param.clear();
+            continue;
+        }
+        param.push(t.clone());
+    }
+    names.extend(param_name(&param));
+    names
+}
+
+/// The name of `param` if it is bound by a plain identifier other than `self`.
+fn param_name(param: &[TokenTree]) -> Option<Ident> {
+    let colon = param.iter().position(|t| is_punct(t, ':'))?;
+    let name = match &param[..colon] {
+        [TokenTree::Ident(name)] => name,
+        [mutable, TokenTree::Ident(name)] if is_ident(mutable, "mut") => name,
+        _ => return None,
+    };
+    match name.to_string().as_str() {
+        "self" | "_" => None,
+        _ => Some(name.clone()),
+    }
+}
+
+/// True if `t` is or contains the `impl` keyword.
+fn contains_impl(t: &TokenTree) -> bool {
+    match t {
+        TokenTree::Group(g) => g.stream().into_iter().any(|t| contains_impl(&t)),
+        t => is_ident(t, "impl"),
+    }
+}
+
+fn is_ident(t: &TokenTree, ident: &str) -> bool {
+    matches!(t, TokenTree::Ident(i) if i.to_string() == ident)
+}
+
+fn is_punct(t: &TokenTree, ch: char) -> bool {
+    matches!(t, TokenTree::Punct(p) if p.as_char() == ch)
+}
+
+fn group(delimiter: Delimiter, stream: TokenStream) -> TokenTree {
+    let mut group = Group::new(delimiter, stream);
+    group.set_span(Span::mixed_site());
+    group.into()
+}
+
+/// Parse `code` into tokens spanned as generated code, so that its local variables do not
+/// clash with the function's and lints treat it as macro output.
+fn generated(code: &str) -> TokenStream {
+    fn respan(t: TokenTree) -> TokenTree {
+        match t {
+            TokenTree::Group(g) => {
+                group(g.delimiter(), g.stream().into_iter().map(respan).collect())
+            }
+            mut t => {
+                t.set_span(Span::mixed_site());
+                t
+            }
+        }
+    }
+    code.parse::<TokenStream>()
+        .unwrap()
+        .into_iter()
+        .map(respan)
+        .collect()
+}
+
+/// A `compile_error!` reporting `msg` at `span`, followed by the unchanged `item`.
+fn error(span: Span, msg: &str, item: TokenStream) -> TokenStream {
+    let mut out: TokenStream = vec![
+        TokenTree::Ident(Ident::new("compile_error", span)),
+        TokenTree::Punct(Punct::new('!', Spacing::Alone)),
+        TokenTree::Group(Group::new(
+            Delimiter::Brace,
+            TokenTree::Literal(Literal::string(msg)).into(),
+        )),
+    ]
+    .into_iter()
+    .map(|mut t| {
+        t.set_span(span);
+        t
+    })
+    .collect();
+    out.extend(item);
+    out
+}
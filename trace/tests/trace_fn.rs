@@ -0,0 +1,144 @@
+//! Expansion of `#[trace_fn]` for the kinds of function it accepts.
+
+use debug_macros::{capture, set_debug, trace_fn};
+use std::fmt::Debug;
+
+struct Counter {
+    n: u32,
+}
+
+impl Counter {
+    #[trace_fn]
+    fn get(&self) -> u32 {
+        self.n
+    }
+
+    #[trace_fn]
+    fn add(&mut self, by: u32) {
+        self.n += by;
+    }
+}
+
+#[trace_fn]
+fn first<T: Debug + Clone>(items: &[T]) -> Option<T> {
+    items.first().cloned()
+}
+
+#[trace_fn]
+fn pair<A, B>(a: A, b: B) -> (A, B)
+where
+    A: Debug,
+    B: Debug,
+{
+    (a, b)
+}
+
+#[trace_fn]
+fn evens(limit: u32) -> impl Debug {
+    (0..limit).step_by(2).collect::<Vec<_>>()
+}
+
+/// A type that does not implement [Debug].
+struct Table(Vec<u8>);
+
+#[trace_fn(skip(table, key))]
+fn lookup(table: &Table, key: &str, index: usize) -> u8 {
+    table.0[index] + key.len() as u8
+}
+
+#[trace_fn]
+fn sign(n: i32) -> &'static str {
+    if n < 0 {
+        return "negative";
+    }
+    "non-negative"
+}
+
+/// The output lines, with the elapsed time at the end of each exit line removed.
+fn lines(cap: &debug_macros::Capture) -> Vec<String> {
+    cap.lines()
+        .into_iter()
+        .map(|line| match line.strip_prefix("debug: exit ") {
+            Some(exit) => format!("debug: exit {}", &exit[..exit.rfind(" (").unwrap()]),
+            None => line,
+        })
+        .collect()
+}
+
+#[test]
+fn test_methods() {
+    if !debug_macros::__STATIC_ENABLED {
+        return;
+    }
+    set_debug(true);
+    let cap = capture();
+    let mut counter = Counter { n: 1 };
+    counter.add(2);
+    assert_eq!(counter.get(), 3);
+    assert_eq!(
+        lines(&cap),
+        vec![
+            "debug: enter add: by = 2",
+            "debug: exit add: ()",
+            "debug: enter get",
+            "debug: exit get: 3",
+        ],
+    );
+}
+
+#[test]
+fn test_generics() {
+    if !debug_macros::__STATIC_ENABLED {
+        return;
+    }
+    set_debug(true);
+    let cap = capture();
+    assert_eq!(first(&["a", "b"]), Some("a"));
+    assert_eq!(pair(1, 'x'), (1, 'x'));
+    assert_eq!(format!("{:?}", evens(5)), "[0, 2, 4]");
+    assert_eq!(
+        lines(&cap),
+        vec![
+            r#"debug: enter first: items = ["a", "b"]"#,
+            r#"debug: exit first: Some("a")"#,
+            "debug: enter pair: a = 1, b = 'x'",
+            "debug: exit pair: (1, 'x')",
+            "debug: enter evens: limit = 5",
+            "debug: exit evens: [0, 2, 4]",
+        ],
+    );
+}
+
+#[test]
+fn test_skip() {
+    if !debug_macros::__STATIC_ENABLED {
+        return;
+    }
+    set_debug(true);
+    let cap = capture();
+    assert_eq!(lookup(&Table(vec![4, 5, 6]), "k", 1), 6);
+    assert_eq!(
+        lines(&cap),
+        vec!["debug: enter lookup: index = 1", "debug: exit lookup: 6"],
+    );
+}
+
+#[test]
+fn test_early_return() {
+    if !debug_macros::__STATIC_ENABLED {
+        return;
+    }
+    set_debug(true);
+    let cap = capture();
+    assert_eq!(sign(-1), "negative");
+    assert_eq!(sign(1), "non-negative");
+    assert_eq!(
+        lines(&cap),
+        vec![
+            "debug: enter sign: n = -1",
+            r#"debug: exit sign: "negative""#,
+            "debug: enter sign: n = 1",
+            r#"debug: exit sign: "non-negative""#,
+        ],
+    );
+}